@@ -14,7 +14,7 @@ use crate::{MIN_FREQ, MAX_FREQ, MIN_BUFFER_SIZE, MAX_BUFFER_SIZE, DEFAULT_BUFFER
 use std::thread;
 use std::time::Duration;
 use std::time::Instant;
-use crate::fft_analysis::FFTConfig;
+use crate::fft_analysis::{FFTConfig, AdaptiveCrosstalkConfig, CrosstalkCanceller};
 use crate::resynth::ResynthConfig;
 
 // This section is protected. Must keep the existing doc comments and struct as is.
@@ -234,6 +234,7 @@ pub fn build_input_stream(
     audio_buffer: Arc<RwLock<CircularBuffer>>,
     _shutdown_flag: Arc<AtomicBool>,
     fft_config: Arc<Mutex<FFTConfig>>,
+    canceller_handoff: Arc<Mutex<crate::fft_analysis::CancellerHandoff>>,
 ) -> Result<pa::Stream<pa::NonBlocking, pa::Input<f32>>, anyhow::Error> {
     let device_info = pa.device_info(device_index)?;
     
@@ -310,6 +311,12 @@ pub fn build_input_stream(
     let last_callback_time = Arc::new(Mutex::new(Instant::now()));
     let last_callback_time_clone = Arc::clone(&last_callback_time);
 
+    // Adaptive crosstalk canceller state lives with the callback; new ones arrive through
+    // the handoff, built by the sampling thread
+    let mut canceller: Option<CrosstalkCanceller> = None;
+    let mut adaptive_settings = AdaptiveCrosstalkConfig::default();
+    let fft_config_callback = Arc::clone(&fft_config);
+
     let stream = pa.open_non_blocking_stream(
         settings,
         move |args: InputCallbackArgs<f32>| {
@@ -335,12 +342,25 @@ pub fn build_input_stream(
             let _non_zero_count = args.buffer.iter().filter(|&&x| x != 0.0).count();
             
             // Process all data, not just non-zero
-            let processed_samples = process_input_samples(
+            let mut processed_samples = process_input_samples(
                 args.buffer,
                 device_channels,
                 &selected_channels
             );
 
+            // Don't block the audio thread on the GUI; keep the last settings if the lock is busy
+            if let Ok(config) = fft_config_callback.try_lock() {
+                adaptive_settings = config.adaptive_crosstalk;
+            }
+            if let Ok(mut handoff) = canceller_handoff.try_lock() {
+                handoff.exchange(&mut canceller, adaptive_settings.enabled);
+            }
+            if adaptive_settings.enabled {
+                if let Some(canceller) = canceller.as_mut() {
+                    canceller.process(&mut processed_samples, adaptive_settings.step_size, adaptive_settings.dominance_db, !adaptive_settings.freeze);
+                }
+            }
+
             if let Ok(mut buffer) = audio_buffer.write() {
                 buffer.push_batch(&processed_samples);
             }
@@ -458,6 +478,7 @@ pub fn start_sampling_thread(
             }
         };
         let device_channels = device_info.max_input_channels as usize;
+        let canceller_handoff = Arc::new(Mutex::new(crate::fft_analysis::CancellerHandoff::default()));
 
         let stream_result = build_input_stream(
             &pa,
@@ -468,6 +489,7 @@ pub fn start_sampling_thread(
             Arc::clone(&main_buffer),
             Arc::clone(&shutdown_flag),
             Arc::clone(&fft_config),
+            Arc::clone(&canceller_handoff),
         );
 
         match stream_result {
@@ -497,6 +519,12 @@ pub fn start_sampling_thread(
                         // Monitor stream health
                         while !shutdown_flag.load(Ordering::SeqCst) {
                             thread::sleep(Duration::from_millis(100));
+
+                            // Build or free crosstalk cancellers here rather than in the callback
+                            let adaptive = fft_config.lock().ok().map(|config| config.adaptive_crosstalk);
+                            if let (Some(adaptive), Ok(mut handoff)) = (adaptive, canceller_handoff.lock()) {
+                                handoff.prepare(&adaptive, selected_channels.len());
+                            }
                             
                             // Check buffer activity
                             if let Ok(buffer) = main_buffer.read() {
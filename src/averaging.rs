@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum AveragingMode {
+    /// Every frame replaces the previous one
+    #[default]
+    Off,
+    /// Exponentially weighted power average with a time constant
+    Exponential,
+    /// Power average over the last N frames
+    Linear,
+    /// Highest magnitude seen per bin, decaying at a fixed rate
+    PeakHold,
+}
+
+impl AveragingMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AveragingMode::Off => "Off",
+            AveragingMode::Exponential => "Exponential",
+            AveragingMode::Linear => "Linear",
+            AveragingMode::PeakHold => "Peak Hold",
+        }
+    }
+}
+
+/// Settings for averaging the magnitude spectrum across frames.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AveragingConfig {
+    pub mode: AveragingMode,
+    pub time_constant: f32,       // Seconds, for exponential averaging
+    pub frames: usize,            // Frame count for linear averaging
+    pub peak_decay_db_per_s: f32, // Fall rate of held peaks
+}
+
+impl Default for AveragingConfig {
+    fn default() -> Self {
+        Self {
+            mode: AveragingMode::Off,
+            time_constant: 0.5,
+            frames: 8,
+            peak_decay_db_per_s: 20.0,
+        }
+    }
+}
+
+/// Running average of one channel's magnitude spectrum.
+///
+/// Exponential and linear modes average power so uncorrelated noise settles to its
+/// RMS level; peak hold works on magnitudes directly.
+#[derive(Debug, Clone, Default)]
+pub struct SpectrumAverager {
+    mode: AveragingMode,
+    /// Averaged power, or held magnitude in peak-hold mode
+    state: Vec<f32>,
+    /// Power spectra of the frames in the linear window and their sum
+    window: VecDeque<Vec<f32>>,
+    window_sum: Vec<f32>,
+}
+
+impl SpectrumAverager {
+    pub fn reset(&mut self) {
+        self.state.clear();
+        self.window.clear();
+        self.window_sum.clear();
+    }
+
+    /// Folds in a new frame of linear magnitudes, `dt` seconds after the previous one,
+    /// and returns the averaged magnitudes.
+    pub fn process(&mut self, magnitudes: &[f32], dt: f32, config: &AveragingConfig) -> Vec<f32> {
+        // Start over when the mode or the FFT size changes
+        if config.mode != self.mode || (!self.state.is_empty() && self.state.len() != magnitudes.len())
+            || self.window.front().is_some_and(|frame| frame.len() != magnitudes.len())
+        {
+            self.reset();
+            self.mode = config.mode;
+        }
+
+        match config.mode {
+            AveragingMode::Off => magnitudes.to_vec(),
+            AveragingMode::Exponential => {
+                if self.state.is_empty() {
+                    self.state = magnitudes.iter().map(|m| m * m).collect();
+                } else {
+                    let alpha = 1.0 - (-dt.max(0.0) / config.time_constant.max(1e-3)).exp();
+                    for (average, m) in self.state.iter_mut().zip(magnitudes) {
+                        *average += alpha * (m * m - *average);
+                    }
+                }
+                self.state.iter().map(|p| p.sqrt()).collect()
+            }
+            AveragingMode::Linear => {
+                let power: Vec<f32> = magnitudes.iter().map(|m| m * m).collect();
+                if self.window_sum.is_empty() {
+                    self.window_sum = vec![0.0; power.len()];
+                }
+                for (sum, p) in self.window_sum.iter_mut().zip(&power) {
+                    *sum += p;
+                }
+                self.window.push_back(power);
+                while self.window.len() > config.frames.max(1) {
+                    if let Some(oldest) = self.window.pop_front() {
+                        for (sum, p) in self.window_sum.iter_mut().zip(&oldest) {
+                            *sum = (*sum - p).max(0.0);
+                        }
+                    }
+                }
+                let count = self.window.len() as f32;
+                self.window_sum.iter().map(|sum| (sum / count).sqrt()).collect()
+            }
+            AveragingMode::PeakHold => {
+                if self.state.is_empty() {
+                    self.state = magnitudes.to_vec();
+                } else {
+                    let decay = 10.0_f32.powf(-config.peak_decay_db_per_s * dt.max(0.0) / 20.0);
+                    for (held, &m) in self.state.iter_mut().zip(magnitudes) {
+                        *held = (*held * decay).max(m);
+                    }
+                }
+                self.state.clone()
+            }
+        }
+    }
+}
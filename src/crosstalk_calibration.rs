@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use log::{info, debug};
+
+pub const CROSSTALK_MATRIX_FILE: &str = "crosstalk_matrix.yaml";
+// Number of logarithmically spaced bands the leakage is measured in
+const CALIBRATION_BANDS: usize = 48;
+const CALIBRATION_MIN_FREQ: f32 = 20.0;
+// How long each source channel is measured for
+const CALIBRATION_DURATION: Duration = Duration::from_secs(4);
+// Bands quieter than this fraction of the loudest source band are ignored,
+// so the ratio is only measured where the source channel is actually excited
+const SOURCE_BAND_FLOOR: f64 = 1e-3;
+
+/// Measured frequency-dependent leakage between every pair of channels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrosstalkMatrix {
+    pub num_channels: usize,
+    pub band_edges: Vec<f32>,
+    /// Linear amplitude ratio indexed as `leakage[source][destination][band]`
+    pub leakage: Vec<Vec<Vec<f32>>>,
+}
+
+impl CrosstalkMatrix {
+    pub fn new(num_channels: usize, sample_rate: f32) -> Self {
+        let nyquist = sample_rate / 2.0;
+        let ratio = (nyquist / CALIBRATION_MIN_FREQ).powf(1.0 / CALIBRATION_BANDS as f32);
+        let band_edges = (0..=CALIBRATION_BANDS)
+            .map(|i| CALIBRATION_MIN_FREQ * ratio.powi(i as i32))
+            .collect();
+        Self {
+            num_channels,
+            band_edges,
+            leakage: vec![vec![vec![0.0; CALIBRATION_BANDS]; num_channels]; num_channels],
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let yaml_str = fs::read_to_string(path)?;
+        serde_yaml::from_str(&yaml_str)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let yaml_str = serde_yaml::to_string(self)?;
+        fs::write(path, yaml_str)?;
+        info!(target: "crosstalk", "Crosstalk matrix saved to {}", path);
+        Ok(())
+    }
+
+    fn band_of(&self, freq: f32) -> Option<usize> {
+        if freq < self.band_edges[0] {
+            return None;
+        }
+        self.band_edges.windows(2).position(|edge| freq < edge[1])
+    }
+
+    /// Leakage from `source` into `destination` at `freq`, as a linear amplitude ratio.
+    pub fn leakage_at(&self, source: usize, destination: usize, freq: f32) -> f32 {
+        if source >= self.num_channels || destination >= self.num_channels || source == destination {
+            return 0.0;
+        }
+        self.band_of(freq)
+            .map(|band| self.leakage[source][destination][band])
+            .unwrap_or(0.0)
+    }
+
+    /// The same leakage on other band edges, taking each new band's value at its
+    /// geometric centre. Used when the sample rate changed since the matrix was saved.
+    fn resampled(&self, band_edges: &[f32]) -> Self {
+        let centers: Vec<f32> = band_edges.windows(2)
+            .map(|edge| (edge[0] * edge[1]).sqrt())
+            .collect();
+        let leakage = (0..self.num_channels)
+            .map(|source| (0..self.num_channels)
+                .map(|dest| centers.iter().map(|&freq| self.leakage_at(source, dest, freq)).collect())
+                .collect())
+            .collect();
+        Self {
+            num_channels: self.num_channels,
+            band_edges: band_edges.to_vec(),
+            leakage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalibrationState {
+    Idle,
+    Measuring { source: usize, started: Instant },
+}
+
+/// Calibration state shared between the GUI (which starts measurements)
+/// and the FFT thread (which feeds it spectra).
+pub struct CrosstalkCalibration {
+    pub matrix: Option<CrosstalkMatrix>,
+    pub state: CalibrationState,
+    pending: CrosstalkMatrix,
+    measured_sources: Vec<bool>,
+    // Per destination channel and band: summed destination and source band power
+    dest_power: Vec<Vec<f64>>,
+    source_power: Vec<Vec<f64>>,
+}
+
+impl CrosstalkCalibration {
+    pub fn new(num_channels: usize, sample_rate: f32) -> Self {
+        let matrix = if Path::new(CROSSTALK_MATRIX_FILE).exists() {
+            match CrosstalkMatrix::load(CROSSTALK_MATRIX_FILE) {
+                Ok(m) if m.num_channels == num_channels => {
+                    info!(target: "crosstalk", "Loaded crosstalk matrix from {}", CROSSTALK_MATRIX_FILE);
+                    Some(m)
+                }
+                Ok(m) => {
+                    info!(target: "crosstalk", "Ignoring crosstalk matrix for {} channels (using {})", m.num_channels, num_channels);
+                    None
+                }
+                Err(e) => {
+                    info!(target: "crosstalk", "Could not load crosstalk matrix: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Self {
+            matrix,
+            state: CalibrationState::Idle,
+            pending: CrosstalkMatrix::new(num_channels, sample_rate),
+            measured_sources: vec![false; num_channels],
+            dest_power: Vec::new(),
+            source_power: Vec::new(),
+        }
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.pending.num_channels
+    }
+
+    pub fn is_measured(&self, source: usize) -> bool {
+        self.measured_sources.get(source).copied().unwrap_or(false)
+    }
+
+    /// Fraction of the current measurement that has elapsed, if one is running.
+    pub fn progress(&self) -> Option<(usize, f32)> {
+        match self.state {
+            CalibrationState::Measuring { source, started } => Some((
+                source,
+                (started.elapsed().as_secs_f32() / CALIBRATION_DURATION.as_secs_f32()).min(1.0),
+            )),
+            CalibrationState::Idle => None,
+        }
+    }
+
+    /// Starts measuring the leakage of `source` into every other channel.
+    /// The user is expected to excite only that channel while it runs.
+    pub fn begin(&mut self, source: usize) {
+        let num_channels = self.pending.num_channels;
+        if source >= num_channels {
+            return;
+        }
+        info!(target: "crosstalk", "Starting crosstalk calibration for source channel {}", source);
+        self.dest_power = vec![vec![0.0; CALIBRATION_BANDS]; num_channels];
+        self.source_power = vec![vec![0.0; CALIBRATION_BANDS]; num_channels];
+        self.state = CalibrationState::Measuring { source, started: Instant::now() };
+    }
+
+    pub fn cancel(&mut self) {
+        self.state = CalibrationState::Idle;
+    }
+
+    /// Feeds one frame of per-channel line spectra (frequency, dB) into a running measurement.
+    pub fn accumulate(&mut self, line_data: &[Vec<(f32, f32)>]) {
+        let (source, started) = match self.state {
+            CalibrationState::Measuring { source, started } => (source, started),
+            CalibrationState::Idle => return,
+        };
+        if line_data.len() != self.pending.num_channels {
+            return;
+        }
+
+        let band_powers: Vec<Vec<f64>> = line_data.iter()
+            .map(|channel| self.band_powers(channel))
+            .collect();
+        let source_bands = &band_powers[source];
+        let loudest = source_bands.iter().cloned().fold(0.0f64, f64::max);
+
+        for (dest, dest_bands) in band_powers.iter().enumerate() {
+            if dest == source {
+                continue;
+            }
+            for band in 0..CALIBRATION_BANDS {
+                if loudest > 0.0 && source_bands[band] > loudest * SOURCE_BAND_FLOOR {
+                    self.dest_power[dest][band] += dest_bands[band];
+                    self.source_power[dest][band] += source_bands[band];
+                }
+            }
+        }
+
+        if started.elapsed() >= CALIBRATION_DURATION {
+            self.finish(source);
+        }
+    }
+
+    fn band_powers(&self, channel: &[(f32, f32)]) -> Vec<f64> {
+        let mut powers = vec![0.0f64; CALIBRATION_BANDS];
+        for &(freq, db) in channel {
+            if let Some(band) = self.pending.band_of(freq) {
+                let amplitude = 10.0f64.powf(db as f64 / 20.0);
+                powers[band] += amplitude * amplitude;
+            }
+        }
+        powers
+    }
+
+    fn finish(&mut self, source: usize) {
+        for dest in 0..self.pending.num_channels {
+            if dest == source {
+                continue;
+            }
+            for band in 0..CALIBRATION_BANDS {
+                let ratio = if self.source_power[dest][band] > 0.0 {
+                    (self.dest_power[dest][band] / self.source_power[dest][band]).sqrt()
+                } else {
+                    0.0
+                };
+                // Leakage can't exceed the direct signal; anything above that is not bleed
+                self.pending.leakage[source][dest][band] = ratio.min(1.0) as f32;
+            }
+            debug!(target: "crosstalk", "Calibrated leakage ch{} -> ch{}: {:?}", source, dest, self.pending.leakage[source][dest]);
+        }
+        self.measured_sources[source] = true;
+        self.state = CalibrationState::Idle;
+        info!(target: "crosstalk", "Finished crosstalk calibration for source channel {}", source);
+    }
+
+    /// Makes the measured rows the active matrix and writes it to disk. Rows that weren't
+    /// measured again are kept from the previous matrix, resampled if its bands differ.
+    pub fn commit(&mut self) -> Result<()> {
+        let mut matrix = match &self.matrix {
+            Some(m) if m.band_edges == self.pending.band_edges => m.clone(),
+            Some(m) => {
+                info!(target: "crosstalk", "Resampling the previous crosstalk matrix onto the current bands");
+                m.resampled(&self.pending.band_edges)
+            }
+            None => self.pending.clone(),
+        };
+        for (source, measured) in self.measured_sources.iter().enumerate() {
+            if *measured {
+                matrix.leakage[source] = self.pending.leakage[source].clone();
+            }
+        }
+        matrix.save(CROSSTALK_MATRIX_FILE)?;
+        self.matrix = Some(matrix);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.matrix = None;
+        if Path::new(CROSSTALK_MATRIX_FILE).exists() {
+            let _ = fs::remove_file(CROSSTALK_MATRIX_FILE);
+        }
+        self.measured_sources.iter_mut().for_each(|m| *m = false);
+        self.state = CalibrationState::Idle;
+    }
+}
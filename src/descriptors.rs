@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+// Fraction of spectral energy below the rolloff frequency
+const ROLLOFF_FRACTION: f32 = 0.85;
+// Floor for level readouts of silent buffers
+const MIN_LEVEL_DB: f32 = -120.0;
+
+/// Per-frame spectral descriptors for one channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpectralDescriptors {
+    pub centroid: f32,  // Hz, magnitude-weighted mean frequency
+    pub spread: f32,    // Hz, magnitude-weighted standard deviation around the centroid
+    pub rolloff: f32,   // Hz, below which 85% of the spectral energy lies
+    pub flatness: f32,  // 0 (tonal) to 1 (noise-like), geometric over arithmetic mean
+    pub flux: f32,      // Rectified magnitude increase since the previous frame
+    pub crest: f32,     // Spectral peak over mean magnitude
+    pub rms_db: f32,    // dBFS RMS of the time-domain buffer
+    pub peak_db: f32,   // dBFS peak of the time-domain buffer
+}
+
+impl SpectralDescriptors {
+    pub const NAMES: [&'static str; 8] = [
+        "Centroid", "Spread", "Rolloff", "Flatness", "Flux", "Crest", "RMS", "Peak",
+    ];
+    pub const UNITS: [&'static str; 8] = ["Hz", "Hz", "Hz", "", "", "", "dB", "dB"];
+
+    /// Values in the same order as `NAMES`.
+    pub fn values(&self) -> [f32; 8] {
+        [
+            self.centroid, self.spread, self.rolloff, self.flatness,
+            self.flux, self.crest, self.rms_db, self.peak_db,
+        ]
+    }
+
+    /// Computes the descriptors from a linear magnitude spectrum (DC first, bins
+    /// `freq_step` apart), the previous frame's spectrum for flux, and the time-domain buffer.
+    pub fn compute(magnitudes: &[f32], previous: Option<&[f32]>, freq_step: f32, signal: &[f32]) -> Self {
+        let mut descriptors = Self {
+            rms_db: MIN_LEVEL_DB,
+            peak_db: MIN_LEVEL_DB,
+            ..Default::default()
+        };
+
+        if !signal.is_empty() {
+            let rms = (signal.iter().map(|s| s * s).sum::<f32>() / signal.len() as f32).sqrt();
+            let peak = signal.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+            descriptors.rms_db = level_db(rms);
+            descriptors.peak_db = level_db(peak);
+        }
+
+        // Skip the DC bin
+        let bins = magnitudes.get(1..).unwrap_or(&[]);
+        let total: f32 = bins.iter().sum();
+        if bins.is_empty() || total <= 0.0 {
+            return descriptors;
+        }
+        let freq = |i: usize| (i + 1) as f32 * freq_step;
+
+        let centroid = bins.iter().enumerate().map(|(i, &m)| freq(i) * m).sum::<f32>() / total;
+        let variance = bins.iter().enumerate()
+            .map(|(i, &m)| (freq(i) - centroid).powi(2) * m)
+            .sum::<f32>() / total;
+        descriptors.centroid = centroid;
+        descriptors.spread = variance.sqrt();
+
+        let energy: f32 = bins.iter().map(|m| m * m).sum();
+        let mut cumulative = 0.0;
+        for (i, &m) in bins.iter().enumerate() {
+            cumulative += m * m;
+            if cumulative >= ROLLOFF_FRACTION * energy {
+                descriptors.rolloff = freq(i);
+                break;
+            }
+        }
+
+        let mean = total / bins.len() as f32;
+        let log_mean = bins.iter().map(|&m| (m + 1e-12).ln()).sum::<f32>() / bins.len() as f32;
+        descriptors.flatness = (log_mean.exp() / mean).clamp(0.0, 1.0);
+        descriptors.crest = bins.iter().fold(0.0f32, |max, &m| max.max(m)) / mean;
+
+        if let Some(previous) = previous.filter(|p| p.len() == magnitudes.len()) {
+            descriptors.flux = bins.iter()
+                .zip(&previous[1..])
+                .map(|(m, p)| (m - p).max(0.0))
+                .sum();
+        }
+
+        descriptors
+    }
+}
+
+fn level_db(value: f32) -> f32 {
+    if value > 0.0 {
+        (20.0 * value.log10()).max(MIN_LEVEL_DB)
+    } else {
+        MIN_LEVEL_DB
+    }
+}
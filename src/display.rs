@@ -1,11 +1,15 @@
 use rayon::prelude::*;
 use log::warn;
 use crate::plot::display_utils;  // Import our utility function
+use crate::fft_analysis::ChannelAnalysis;
+use crate::tuning::Tuning;
 
 pub struct SpectralDisplay {
     channels: Vec<Vec<(f32, f32)>>,
     fft_line_data: Vec<Vec<(f32, f32)>>,
     num_partials: usize,  // Add field to track the number of partials
+    analysis: Vec<ChannelAnalysis>,
+    tuning: Option<Tuning>,  // Set to add a note-name line per channel
 }
 
 impl SpectralDisplay {
@@ -21,6 +25,8 @@ impl SpectralDisplay {
             channels: channels.to_vec(),
             fft_line_data: Vec::new(),
             num_partials,
+            analysis: Vec::new(),
+            tuning: None,
         }
     }
 
@@ -31,13 +37,46 @@ impl SpectralDisplay {
         self.fft_line_data = fft_data;
     }
 
+    pub fn update_analysis(&mut self, analysis: Vec<ChannelAnalysis>) {
+        self.analysis = analysis;
+    }
+
+    pub fn update_tuning(&mut self, tuning: Tuning) {
+        self.tuning = Some(tuning);
+    }
+
     pub fn format_all(&self) -> Vec<String> {
         self.channels.par_iter()
             .enumerate()
             .map(|(channel, values)| {
                 // Use our utility function for consistent formatting
                 let magnitudes = display_utils::format_partials(values, self.num_partials);
-                format!("Channel {}: [{}]", channel + 1, magnitudes)
+                let analysis = self.analysis.get(channel);
+                let pitch = match analysis.and_then(|a| a.pitch) {
+                    Some(p) => format!(" f0: {:.2} Hz ({:.2})", p.f0, p.confidence),
+                    None => String::new(),
+                };
+                let inharmonicity = match analysis.and_then(|a| a.harmonics.inharmonicity) {
+                    Some(b) => format!(" B: {:.2e}", b),
+                    None => String::new(),
+                };
+                let last_onset = match analysis.and_then(|a| a.last_onset) {
+                    Some(onset) => format!(" Onset: {:.2} s", onset.time),
+                    None => String::new(),
+                };
+                let mut line = format!("Channel {}:{}{}{} [{}]", channel + 1, pitch, inharmonicity, last_onset, magnitudes);
+                if let Some(analysis) = analysis {
+                    line.push_str(&format!("\n    Descriptors: {}", display_utils::format_descriptors(&analysis.descriptors)));
+                }
+                if let Some(analysis) = analysis.filter(|a| a.pitch.is_some()) {
+                    let labels = display_utils::format_harmonics(&analysis.harmonics, self.num_partials);
+                    line.push_str(&format!("\n    Harmonics: [{}]", labels));
+                }
+                if let Some(tuning) = &self.tuning {
+                    let notes = display_utils::format_notes(values, tuning, self.num_partials);
+                    line.push_str(&format!("\n    Notes: [{}]", notes));
+                }
+                line
             })
             .collect()
     }
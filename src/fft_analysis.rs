@@ -18,12 +18,23 @@ use std::collections::VecDeque;
 use std::time::Instant;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
-use rustfft::num_complex::Complex;
 use tokio::sync::broadcast; // Added import
 use serde::{Serialize, Deserialize};
+use crate::crosstalk_calibration::{CrosstalkCalibration, CrosstalkMatrix};
+use crate::pitch::{estimate_pitch, PitchEstimate};
+use crate::harmonics::{analyze_harmonics, HarmonicAnalysis};
+use crate::descriptors::SpectralDescriptors;
+use crate::residual::NoiseEnvelope;
+use crate::onset::{detection_value, OnsetConfig, OnsetDetector, OnsetEvent};
+use crate::partial_tracker::PartialTracker;
+use crate::averaging::{AveragingConfig, SpectrumAverager};
 
 // Change the constant declaration to be public
-pub const MAX_SPECTROGRAPH_HISTORY: usize = 500;
+pub const MAX_SPECTROGRAPH_SECONDS: f64 = 30.0;
+pub const MAX_DESCRIPTOR_HISTORY: usize = 2000;
+pub const MAX_ONSET_HISTORY: usize = 500;
+// Lowest dBFS value kept in the line spectrum
+const LINE_FLOOR_DB: f32 = -160.0;
 
 // Add a type alias for clarity
 type PartialsData = Vec<Vec<(f32, f32)>>;
@@ -33,14 +44,14 @@ type PartialsData = Vec<Vec<(f32, f32)>>;
 pub struct FFTConfig {
     pub min_frequency: f64,
     pub max_frequency: f64,
-    pub magnitude_threshold: f64,  // Renamed from db_threshold
+    pub magnitude_threshold: f64,  // Lowest partial level in dBFS
     pub min_freq_spacing: f64,  // Add new parameter
-    #[allow(dead_code)]
     pub num_channels: usize,
     pub frames_per_buffer: u32,
     pub crosstalk_threshold: f32,  // Add crosstalk threshold (0.0 to 1.0)
     pub crosstalk_reduction: f32,  // Add reduction factor (0.0 to 1.0)
     pub crosstalk_enabled: bool,  // Add enable flag for crosstalk filtering
+    pub crosstalk_mode: CrosstalkMode,  // Heuristic guess or measured bleed matrix
     pub harmonic_tolerance: f32,  // Add this field - controls how closely a frequency must match a harmonic
     pub window_type: WindowType,
     pub root_freq_min: f32,  // Add this (default: 20.0)
@@ -48,6 +59,12 @@ pub struct FFTConfig {
     pub freq_match_distance: f32,  // Maximum Hz difference to consider frequencies as matching
     pub num_partials: usize,  // Add configurable number of partials
     pub gain: f32,  // Add gain parameter for signal amplification
+    pub channel_overrides: Vec<ChannelOverrides>,  // Per-channel settings, indexed by selected channel
+    pub adaptive_crosstalk: AdaptiveCrosstalkConfig,  // Time-domain canceller applied before the FFT
+    pub pitch_confidence_threshold: f32,  // Use the estimated f0 as crosstalk root above this confidence
+    pub onset: OnsetConfig,  // Per-channel onset detection on the frame spectra
+    pub averaging: AveragingConfig,  // Averaging or peak hold of the spectrum the partials are picked from
+    pub averaging_reset: bool,  // Set by the GUI to clear the averages; cleared by the FFT thread
 }
 
 impl Default for FFTConfig {
@@ -55,13 +72,14 @@ impl Default for FFTConfig {
         Self {
             min_frequency: MIN_FREQ,
             max_frequency: *MAX_FREQ,
-            magnitude_threshold: 6.0, 
+            magnitude_threshold: -80.0,
             min_freq_spacing: 20.0,
             num_channels: 1,
             frames_per_buffer: 512,
             crosstalk_threshold: 0.3,
             crosstalk_reduction: 0.5,
             crosstalk_enabled: false,
+            crosstalk_mode: CrosstalkMode::Heuristic,
             harmonic_tolerance: 0.03,
             root_freq_min: 20.0,
             root_freq_max: (DEFAULT_BUFFER_SIZE as f32 / 4.0),
@@ -69,10 +87,59 @@ impl Default for FFTConfig {
             window_type: WindowType::Hanning,
             num_partials: DEFAULT_NUM_PARTIALS, // Use default value from main.rs
             gain: 1.0,  // Default gain is 1.0 (no amplification)
+            channel_overrides: Vec::new(),
+            adaptive_crosstalk: AdaptiveCrosstalkConfig::default(),
+            pitch_confidence_threshold: 0.8,
+            onset: OnsetConfig::default(),
+            averaging: AveragingConfig::default(),
+            averaging_reset: false,
         }
     }
 }
 
+/// How frequency-domain crosstalk filtering decides what is bleed.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum CrosstalkMode {
+    /// Harmonicity against each channel's root frequency
+    #[default]
+    Heuristic,
+    /// Subtract the leakage predicted by the measured crosstalk matrix
+    Calibrated,
+}
+
+/// Per-channel overrides of the analysis settings.
+/// A `None` field falls back to the global value in `FFTConfig`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChannelOverrides {
+    pub min_frequency: Option<f64>,
+    pub max_frequency: Option<f64>,
+    pub magnitude_threshold: Option<f64>,
+    pub min_freq_spacing: Option<f64>,
+    pub gain: Option<f32>,
+    pub num_partials: Option<usize>,
+}
+
+impl FFTConfig {
+    /// Returns the effective configuration for one channel, with its overrides applied.
+    ///
+    /// The per-channel partial count is capped at the global `num_partials`, which
+    /// fixes the shared memory layout announced in the control file at startup.
+    pub fn for_channel(&self, channel: usize) -> FFTConfig {
+        let mut config = self.clone();
+        if let Some(overrides) = self.channel_overrides.get(channel) {
+            if let Some(v) = overrides.min_frequency { config.min_frequency = v; }
+            if let Some(v) = overrides.max_frequency { config.max_frequency = v; }
+            if let Some(v) = overrides.magnitude_threshold { config.magnitude_threshold = v; }
+            if let Some(v) = overrides.min_freq_spacing { config.min_freq_spacing = v; }
+            if let Some(v) = overrides.gain { config.gain = v; }
+            if let Some(v) = overrides.num_partials {
+                config.num_partials = v.clamp(1, self.num_partials);
+            }
+        }
+        config
+    }
+}
+
 // Define a macro to log specifically under target "crosstalk"
 #[macro_export]
 macro_rules! crosstalk_info {
@@ -87,6 +154,115 @@ pub struct FFTData {
     line_data: Vec<Vec<(f32, f32)>>,
 }
 
+/// Which rule of the crosstalk filter attenuated a partial.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrosstalkRule {
+    BothHarmonic,      // Both channels claim it as a harmonic; the weaker claim loses
+    OneHarmonic,       // Only one channel claims it; the other copy is reduced
+    MagnitudeCompare,  // Neither is harmonic; the significantly weaker copy is reduced
+    Calibrated,        // Leakage predicted by the measured crosstalk matrix
+}
+
+impl CrosstalkRule {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CrosstalkRule::BothHarmonic => "Both harmonic",
+            CrosstalkRule::OneHarmonic => "One harmonic",
+            CrosstalkRule::MagnitudeCompare => "Magnitude compare",
+            CrosstalkRule::Calibrated => "Calibrated",
+        }
+    }
+}
+
+/// A single attenuation made by the crosstalk filter.
+#[derive(Debug, Clone)]
+pub struct CrosstalkDecision {
+    pub channel: usize,
+    pub freq: f32,
+    /// The channel and frequency it was matched against, if any
+    pub matched: Option<(usize, f32)>,
+    pub rule: CrosstalkRule,
+    pub before: f32,
+    pub after: f32,
+}
+
+/// What the crosstalk filter did on one frame.
+#[derive(Debug, Clone, Default)]
+pub struct CrosstalkReport {
+    /// Root frequency used per channel (0.0 when none was found)
+    pub roots: Vec<f32>,
+    pub decisions: Vec<CrosstalkDecision>,
+}
+
+/// When the audio behind an analysis frame was captured, for measuring latency.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameTiming {
+    /// Arrival of the newest input block in the frame
+    pub captured_at: Instant,
+    /// Seconds of one input block, which the newest sample waited out before arriving
+    pub input_block_seconds: f32,
+    /// Half the analysis window in seconds: how far the window's centre lags its newest sample
+    pub window_seconds: f32,
+}
+
+/// Per-channel analysis published alongside the partials.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelAnalysis {
+    pub pitch: Option<PitchEstimate>,
+    pub harmonics: HarmonicAnalysis,
+    pub descriptors: SpectralDescriptors,
+    /// Onset detected on this frame
+    pub onset: Option<OnsetEvent>,
+    /// Most recent onset on this channel, kept so polling consumers can spot new ones
+    pub last_onset: Option<OnsetEvent>,
+    /// Track ID per partial, `None` for empty slots
+    pub tracks: Vec<Option<u32>>,
+    /// Residual spectrum once the partials are taken out, for noise resynthesis
+    pub noise: NoiseEnvelope,
+    pub timing: Option<FrameTiming>,
+}
+
+impl ChannelAnalysis {
+    /// Number of f32 values in one channel's shared memory record.
+    pub fn shared_memory_record_len(num_partials: usize) -> usize {
+        3 + 2 * num_partials + SpectralDescriptors::NAMES.len() + 2 + num_partials
+    }
+
+    /// Flattens the analysis into the fixed-size per-channel record written to shared memory:
+    /// f0 (Hz, 0 when unvoiced), f0 confidence, inharmonicity B, then per partial
+    /// harmonic number (0 when unlabelled) and cents deviation, then the spectral descriptors,
+    /// the time and strength of the last onset (0 when none yet), and per partial its track ID.
+    pub fn shared_memory_record(&self, num_partials: usize) -> Vec<f32> {
+        let pitch = self.pitch.unwrap_or_default();
+        let mut record = vec![pitch.f0, pitch.confidence, self.harmonics.inharmonicity.unwrap_or(0.0)];
+        for i in 0..num_partials {
+            match self.harmonics.partials.get(i).copied().flatten() {
+                Some(label) => record.extend_from_slice(&[label.harmonic as f32, label.cents]),
+                None => record.extend_from_slice(&[0.0, 0.0]),
+            }
+        }
+        record.extend_from_slice(&self.descriptors.values());
+        let last_onset = self.last_onset.unwrap_or_default();
+        record.extend_from_slice(&[last_onset.time as f32, last_onset.strength]);
+        for i in 0..num_partials {
+            record.push(self.tracks.get(i).copied().flatten().unwrap_or(0) as f32);
+        }
+        record
+    }
+}
+
+pub type AnalysisData = Vec<ChannelAnalysis>;
+
+/// State the FFT thread carries from one frame to the next for analyses that compare frames.
+#[derive(Default)]
+pub struct AnalysisState {
+    previous_magnitudes: Vec<Vec<f32>>,
+    onset_detectors: Vec<OnsetDetector>,
+    partial_tracker: PartialTracker,
+    averagers: Vec<SpectrumAverager>,
+    last_frame_time: Option<f64>,
+}
+
 // Add near the top of the file with other structs
 pub struct CurrentPartials {
     pub data: Vec<Vec<(f32, f32)>>,
@@ -100,15 +276,21 @@ impl CurrentPartials {
     }
 }
 
-/// Computes both partial data and full FFT line data
+/// Computes partial data, full FFT line data and the linear magnitude spectrum
+/// (amplitude-normalised by N/2) used by the frame descriptors.
+/// Line data and partials come from the spectrum after `averager`, in dBFS; the returned
+/// magnitude spectrum is the current frame's own, so flux and onsets stay sharp.
 fn compute_all_fft_data(
     all_channel_data: &[Vec<f32>],
     channel_index: usize,
     sample_rate: u32, 
     config: &FFTConfig,
-) -> (Vec<(f32, f32)>, Vec<(f32, f32)>) {
+    averager: &mut SpectrumAverager,
+    dt: f32,
+) -> (Vec<(f32, f32)>, Vec<(f32, f32)>, Vec<f32>) {
     let signal = &all_channel_data[channel_index];
     let signal_len = signal.len(); // Store original signal length
+    let channel_config = config.for_channel(channel_index);
 
     // Apply window to signal
     let windowed_signal = apply_window(&signal, config.window_type);
@@ -121,35 +303,47 @@ fn compute_all_fft_data(
     
     if let Err(e) = fft.process(&mut indata, &mut complex_spectrum_output) {
         error!("FFT computation error: {:?}", e);
-        return (vec![(0.0, 0.0); config.num_partials], Vec::new());
+        return (vec![(0.0, 0.0); config.num_partials], Vec::new(), Vec::new());
     }
 
-    // Convert to dB scale for line_data
+    let raw_magnitudes: Vec<f32> = complex_spectrum_output.iter().map(|c| c.norm()).collect();
+    // Relative to a full-scale sine, so levels don't depend on the FFT length or window
+    let full_scale = full_scale_magnitude(signal_len, config.window_type);
+    let normalised_magnitudes: Vec<f32> = raw_magnitudes.iter().map(|m| m / full_scale).collect();
+    let averaged_magnitudes = averager.process(&normalised_magnitudes, dt, &config.averaging);
+
+    // Convert to dBFS for line_data
     let freq_step = sample_rate as f32 / signal_len as f32; // Use original signal_len
-    let line_data: Vec<(f32, f32)> = complex_spectrum_output
+    let line_data: Vec<(f32, f32)> = averaged_magnitudes
         .par_iter()
         .enumerate()
-        .map(|(i, &complex_val)| {
+        .map(|(i, &magnitude)| {
             let frequency = i as f32 * freq_step;
-            let magnitude = (complex_val.re * complex_val.re + complex_val.im * complex_val.im).sqrt();
             let db = if magnitude > 1e-10 {
                 20.0 * (magnitude + 1e-10).log10() // Add epsilon for stability
             } else {
-                0.0 // Or a very small dB value like -120.0
+                LINE_FLOOR_DB
             };
-            (frequency, db.max(0.0)) // Ensure non-negative dB for line plot
+            (frequency, db.max(LINE_FLOOR_DB))
         })
         .collect();
 
     // Compute partials (now linear magnitude) using the new function
-    let partials = extract_partials_from_spectrum(
-        &complex_spectrum_output, 
+    let mut partials = extract_partials_from_spectrum(
+        &averaged_magnitudes, 
         sample_rate, 
         signal_len, // Pass original signal length
-        config
+        &channel_config
     );
+    // Keep every channel at the global partial count so downstream consumers stay rectangular
+    partials.resize(config.num_partials, (0.0, 0.0));
 
-    (partials, line_data)
+    let amplitude_scale = 2.0 / signal_len as f32;
+    let magnitudes: Vec<f32> = raw_magnitudes.iter()
+        .map(|m| m * amplitude_scale)
+        .collect();
+
+    (partials, line_data, magnitudes)
 }
 
 /// Processes audio data to extract spectral information.
@@ -157,12 +351,20 @@ fn compute_all_fft_data(
 /// 1. Partials data (frequency, magnitude) for each channel
 /// 2. FFT line data for visualization
 /// 3. Spectrograph data for history tracking
+/// 4. A report of what the crosstalk filter attenuated
+/// 5. Per-channel analysis (pitch, harmonic labels, descriptors, onsets, partial tracks,
+///    residual noise envelope)
+///
+/// `frame_time` is the frame's time in seconds since the analyzer started, used to stamp onsets.
 pub fn process_audio_data(
     audio_data: &[f32],
     config: &FFTConfig,
     num_channels: usize,
     sample_rate: u32,
-) -> Result<(PartialsData, Vec<Vec<(f32, f32)>>, Vec<(f64, f32)>), String> {
+    crosstalk_matrix: Option<&CrosstalkMatrix>,
+    state: &mut AnalysisState,
+    frame_time: f64,
+) -> Result<(PartialsData, Vec<Vec<(f32, f32)>>, Vec<Vec<(u32, f32, f32)>>, CrosstalkReport, AnalysisData), String> {
     if audio_data.is_empty() {
         return Err("Empty audio data".to_string());
     }
@@ -176,24 +378,88 @@ pub fn process_audio_data(
         return Err("Failed to extract channel data".to_string());
     }
 
+    let dt = state.last_frame_time.map_or(0.0, |last| (frame_time - last) as f32);
+    state.last_frame_time = Some(frame_time);
+    if config.averaging_reset {
+        state.averagers.iter_mut().for_each(SpectrumAverager::reset);
+    }
+    state.averagers.resize_with(num_channels, SpectrumAverager::default);
+
     // Process each channel to get both partial and line data
     let mut all_channels_partials = Vec::with_capacity(num_channels);
     let mut all_channels_line_data = Vec::with_capacity(num_channels);
+    let mut all_channels_magnitudes = Vec::with_capacity(num_channels);
 
     for channel_index in 0..num_channels {
-        let (partials, line_data) = compute_all_fft_data(
+        let (partials, line_data, magnitudes) = compute_all_fft_data(
             &channel_buffers,
             channel_index,
             sample_rate,
-            config
+            config,
+            &mut state.averagers[channel_index],
+            dt,
         );
         
         all_channels_partials.push(partials);
         all_channels_line_data.push(line_data);
+        all_channels_magnitudes.push(magnitudes);
     }
 
+    // Estimate each channel's fundamental over the crosstalk root range
+    // and describe each frame against the previous one
+    let freq_step = sample_rate as f32 / channel_buffers[0].len() as f32;
+    let mut analysis: AnalysisData = channel_buffers.iter()
+        .zip(&all_channels_magnitudes)
+        .enumerate()
+        .map(|(channel_index, (signal, magnitudes))| ChannelAnalysis {
+            pitch: estimate_pitch(signal, sample_rate, config.root_freq_min, config.root_freq_max),
+            descriptors: SpectralDescriptors::compute(
+                magnitudes,
+                state.previous_magnitudes.get(channel_index).map(|p| p.as_slice()),
+                freq_step,
+                signal,
+            ),
+            ..Default::default()
+        })
+        .collect();
+
+    // Pick onsets from each channel's detection function
+    if config.onset.enabled {
+        state.onset_detectors.resize_with(num_channels, OnsetDetector::default);
+        for ((channel_analysis, magnitudes), detector) in analysis.iter_mut()
+            .zip(&all_channels_magnitudes)
+            .zip(state.onset_detectors.iter_mut())
+        {
+            let value = detection_value(config.onset.method, magnitudes, channel_analysis.descriptors.flux);
+            channel_analysis.onset = detector.process(frame_time, value, &config.onset);
+            channel_analysis.last_onset = detector.last_onset();
+        }
+    }
+    state.previous_magnitudes = all_channels_magnitudes;
+
+    // Confident pitch estimates replace the loudest-partial guess as crosstalk root
+    let pitch_roots: Vec<Option<f32>> = analysis.iter()
+        .map(|a| a.pitch
+            .filter(|p| p.confidence >= config.pitch_confidence_threshold)
+            .map(|p| p.f0))
+        .collect();
+
     // Apply crosstalk filtering if enabled
-    let filtered_partials: PartialsData = if config.crosstalk_enabled {
+    let (filtered_partials, crosstalk_report): (PartialsData, CrosstalkReport) = if config.crosstalk_enabled && config.crosstalk_mode == CrosstalkMode::Calibrated {
+        match crosstalk_matrix {
+            Some(matrix) => filter_crosstalk_calibrated(
+                &all_channels_partials,
+                &all_channels_line_data,
+                matrix,
+                config.crosstalk_reduction,
+                config,
+            ),
+            None => {
+                crosstalk_info!("Calibrated crosstalk mode selected but no matrix measured; leaving partials unfiltered");
+                (all_channels_partials.clone(), CrosstalkReport::default())
+            }
+        }
+    } else if config.crosstalk_enabled {
         filter_crosstalk_frequency_domain(
             &mut all_channels_partials.clone(),
             config.crosstalk_threshold,
@@ -202,23 +468,45 @@ pub fn process_audio_data(
             config.root_freq_min,
             config.root_freq_max,
             config.freq_match_distance,
-            sample_rate
+            sample_rate,
+            &pitch_roots,
         )
     } else {
-        all_channels_partials.clone()
+        (all_channels_partials.clone(), CrosstalkReport::default())
     };
 
-    // Generate spectrograph data
-    let linear_threshold = 10.0_f32.powf(config.magnitude_threshold as f32 / 20.0);
-    let spectrograph_data: Vec<(f64, f32)> = filtered_partials.iter()
-        .flat_map(|channel_data| {
+    // Label the filtered partials against each channel's pitch and link them into tracks,
+    // breaking every track at an onset when onsets are hard boundaries, and measure what
+    // is left of the spectrum without them
+    for (channel_index, (channel_analysis, channel_partials)) in analysis.iter_mut().zip(&filtered_partials).enumerate() {
+        let f0 = channel_analysis.pitch.map(|p| p.f0).unwrap_or(0.0);
+        channel_analysis.harmonics = analyze_harmonics(channel_partials, f0);
+        let boundary = config.onset.hard_boundaries && channel_analysis.onset.is_some();
+        channel_analysis.tracks = state.partial_tracker.update(channel_index, channel_partials, boundary);
+        let channel_config = config.for_channel(channel_index);
+        channel_analysis.noise = NoiseEnvelope::compute(
+            &all_channels_line_data[channel_index],
+            channel_partials,
+            channel_config.min_frequency as f32,
+            channel_config.max_frequency as f32,
+        );
+    }
+
+    // Generate the spectrograph's track overlay, thresholding each channel with its own setting
+    let spectrograph_data: Vec<Vec<(u32, f32, f32)>> = filtered_partials.iter()
+        .zip(&analysis)
+        .enumerate()
+        .map(|(channel_index, (channel_data, channel_analysis))| {
+            let channel_threshold = config.for_channel(channel_index).magnitude_threshold as f32;
             channel_data.iter()
-                .filter(move |&&(_freq, magnitude)| magnitude >= linear_threshold)
-                .map(|&(freq, magnitude)| (freq as f64, magnitude))
+                .zip(&channel_analysis.tracks)
+                .filter(|&(&(_freq, db), _)| db >= channel_threshold)
+                .filter_map(|(&(freq, db), &track)| track.map(|id| (id, freq, db)))
+                .collect()
         })
         .collect();
 
-    Ok((filtered_partials, all_channels_line_data, spectrograph_data))
+    Ok((filtered_partials, all_channels_line_data, spectrograph_data, crosstalk_report, analysis))
 }
 
 /// Spawns a thread to continuously process FFT data and update the plot.
@@ -232,11 +520,14 @@ pub fn start_fft_processing(
     partials_tx: broadcast::Sender<PartialsData>,
     spectrograph_history: Option<Arc<Mutex<VecDeque<SpectrographSlice>>>>,
     start_time: Option<Arc<Instant>>,
+    crosstalk_calibration: Arc<Mutex<CrosstalkCalibration>>,
+    analysis_tx: broadcast::Sender<AnalysisData>,
 ) {
     // Add a counter to track FFT processing cycles
     let mut fft_cycle_count = 0;
     let mut last_log_time = Instant::now();
     let mut last_successful_process = Instant::now();
+    let mut analysis_state = AnalysisState::default();
 
     info!("FFT processing thread started");
     debug!("FFT thread initialized with {} channels at {} Hz", selected_channels.len(), sample_rate);
@@ -302,9 +593,9 @@ pub fn start_fft_processing(
             continue;
         }
 
-        // Get a copy of the audio data for FFT processing
-        let audio_data = if let Ok(buffer) = audio_buffer.read() {
-            buffer.clone_data()
+        // Get a copy of the audio data for FFT processing, and when its newest block arrived
+        let (audio_data, captured_at) = if let Ok(buffer) = audio_buffer.read() {
+            (buffer.clone_data(), Instant::now() - buffer.check_activity())
         } else {
             continue;
         };
@@ -314,30 +605,71 @@ pub fn start_fft_processing(
         }
 
         // Get the current FFT configuration
-        let fft_config_copy = if let Ok(config) = fft_config.lock() {
-            config.clone()
+        let fft_config_copy = if let Ok(mut config) = fft_config.lock() {
+            let copy = config.clone();
+            config.averaging_reset = false;  // One frame sees a reset request
+            copy
         } else {
             continue;
         };
 
+        // Take a copy of the measured crosstalk matrix, if any
+        let crosstalk_matrix = if fft_config_copy.crosstalk_mode == CrosstalkMode::Calibrated {
+            crosstalk_calibration.lock().ok().and_then(|c| c.matrix.clone())
+        } else {
+            None
+        };
+
+        let frame_time = start_time.as_ref().map(|start| start.elapsed().as_secs_f64()).unwrap_or(0.0);
+
         // Process the audio data to extract spectral information
         match process_audio_data(
             &audio_data,
             &fft_config_copy,
             selected_channels.len(),
             sample_rate,
+            crosstalk_matrix.as_ref(),
+            &mut analysis_state,
+            frame_time,
         ) {
-            Ok((partials, fft_data, spectrograph_data)) => {
+            Ok((partials, fft_data, spectrograph_data, crosstalk_report, mut analysis)) => {
                 last_successful_process = Instant::now();
+
+                let fft_len = audio_data.len() / selected_channels.len().max(1);
+                let timing = FrameTiming {
+                    captured_at,
+                    input_block_seconds: fft_config_copy.frames_per_buffer as f32 / sample_rate as f32,
+                    window_seconds: fft_len as f32 / 2.0 / sample_rate as f32,
+                };
+                for channel_analysis in analysis.iter_mut() {
+                    channel_analysis.timing = Some(timing);
+                }
+
+                // Feed a running crosstalk calibration with the unfiltered line spectra
+                if let Ok(mut calibration) = crosstalk_calibration.lock() {
+                    calibration.accumulate(&fft_data);
+                }
                 
                 // Update the spectrum app with the FFT line data
                 if let Ok(mut app) = spectrum_app.lock() {
                     app.update_fft_line_data(fft_data.clone());
+                    app.update_crosstalk_report(crosstalk_report);
+                    app.push_descriptors(frame_time, analysis.iter().map(|a| a.descriptors).collect());
+                    for (channel, channel_analysis) in analysis.iter().enumerate() {
+                        if let Some(onset) = channel_analysis.onset {
+                            debug!("Onset on channel {} at {:.3}s (strength {:.4})", channel, onset.time, onset.strength);
+                            app.push_onset(channel, onset);
+                        }
+                    }
+                    app.update_analysis(analysis.clone());
                     debug!("Updated spectrum app with new FFT line data: {} channels", fft_data.len());
                 } else {
                     debug!("Failed to lock spectrum_app to update FFT line data");
                 }
 
+                // Publish the per-channel analysis; having no subscribers is not an error
+                let _ = analysis_tx.send(analysis);
+
                 // Send the partials data to any subscribers (GUI and resynth)
                 let receiver_count = partials_tx.receiver_count();
                 match partials_tx.send(partials.clone()) {
@@ -371,22 +703,28 @@ pub fn start_fft_processing(
                 // Update the spectrograph history if available
                 if let Some(history) = &spectrograph_history {
                     if let Ok(mut history) = history.lock() {
-                        let current_time = if let Some(start) = &start_time {
-                            start.elapsed().as_secs_f64()
-                        } else {
-                            0.0
-                        };
+                        // Keep the full spectrum only up to the max frequency to bound memory
+                        let fft_len = audio_data.len() / selected_channels.len().max(1);
+                        let bin_hz = sample_rate as f32 / fft_len.max(1) as f32;
+                        let max_frequency = fft_config_copy.max_frequency as f32;
+                        let spectrum: Vec<Vec<f32>> = fft_data.iter()
+                            .map(|line| line.iter()
+                                .take_while(|&&(freq, _)| freq <= max_frequency + bin_hz)
+                                .map(|&(_, db)| db)
+                                .collect())
+                            .collect();
 
                         history.push_back(SpectrographSlice {
-                            time: current_time,
-                            data: spectrograph_data.clone(),
+                            time: frame_time,
+                            bin_hz,
+                            spectrum,
+                            partials: spectrograph_data,
                         });
                         
-                        debug!("Updated spectrograph history: {} entries, {} data points in latest slice", 
-                               history.len(), spectrograph_data.len());
+                        debug!("Updated spectrograph history: {} entries", history.len());
 
-                        // Limit the history size
-                        while history.len() > MAX_SPECTROGRAPH_HISTORY {
+                        // Limit the history to the longest selectable time span
+                        while history.front().is_some_and(|oldest| frame_time - oldest.time > MAX_SPECTROGRAPH_SECONDS) {
                             history.pop_front();
                         }
                     } else {
@@ -432,69 +770,187 @@ pub fn extract_channel_data(buffer: &[f32], channel: usize, num_channels: usize)
         .collect()
 }
 
-/// Reduces crosstalk between channels
-pub fn filter_crosstalk(
-    channel_data: &[Vec<f32>],
-    threshold: f32,
-    reduction: f32
-) -> Vec<Vec<f32>> {
-    if channel_data.is_empty() {
-        return Vec::new();
+/// Settings for the adaptive time-domain crosstalk canceller.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveCrosstalkConfig {
+    pub enabled: bool,
+    pub step_size: f32,       // NLMS convergence rate (0.0 to 1.0)
+    pub filter_length: usize, // FIR taps per channel pair
+    pub freeze: bool,         // Keep filtering with the learned paths but stop adapting
+    pub dominance_db: f32,    // How far the sources must stand above a channel's residual for full-rate adaptation
+}
+
+impl Default for AdaptiveCrosstalkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            step_size: 0.05,
+            filter_length: 32,
+            freeze: false,
+            dominance_db: 6.0,
+        }
     }
+}
 
-    let num_channels = channel_data.len();
-    let samples_per_channel = channel_data[0].len();
-    let mut filtered = vec![vec![0.0; samples_per_channel]; num_channels];
+// Samples for the levels in the double-talk control to rise and to fall: a channel's own
+// sound has to slow adaptation within a few samples of starting, and keep it slowed
+// through its quieter moments
+const DOMINANCE_ATTACK_SAMPLES: f32 = 4.0;
+const DOMINANCE_RELEASE_SAMPLES: f32 = 2048.0;
+// Most weights (taps times channels squared) a canceller may have, so its cost per
+// sample stays bounded as channels are added
+pub const MAX_CANCELLER_WEIGHTS: usize = 4096;
+
+/// Adaptive NLMS canceller that learns an FIR bleed path from every channel into
+/// every other channel and subtracts the predicted bleed from interleaved samples.
+///
+/// The step is scaled down smoothly while a channel's residual is loud next to the other
+/// channels, a soft form of the double-talk detection in echo cancellers: once the bleed
+/// is cancelled, what is left is the channel's own sound, and learning from it would
+/// drag the filters away. While the bleed is still uncancelled the residual follows the
+/// sources, so adaptation runs at close to the full step. Channels meant to hear the same
+/// sound at different levels (a close and a room mic) look like bleed to the canceller
+/// and should not be cancelled.
+pub struct CrosstalkCanceller {
+    num_channels: usize,
+    filter_length: usize,
+    // weights[dest][source] holds `filter_length` taps, newest sample first
+    weights: Vec<Vec<Vec<f32>>>,
+    // Per-channel input history of twice `filter_length`, each sample written twice so
+    // the newest `filter_length` are always contiguous from `history_pos`
+    history: Vec<Vec<f32>>,
+    history_pos: usize,
+    // Running energy of each channel's history, used to normalise the step
+    history_energy: Vec<f32>,
+    // Power envelopes of each channel's input and residual, for the double-talk control
+    level: Vec<f32>,
+    residual_level: Vec<f32>,
+    errors: Vec<f32>,
+}
 
-    // Calculate channel energy levels for normalization
-    let channel_energy: Vec<f32> = channel_data.iter()
-        .map(|channel| {
-            channel.iter().map(|&s| s * s).sum::<f32>().sqrt() / channel.len() as f32
-        })
-        .collect();
+impl CrosstalkCanceller {
+    pub fn new(num_channels: usize, filter_length: usize) -> Self {
+        let filter_length = Self::capped_length(num_channels, filter_length);
+        Self {
+            num_channels,
+            filter_length,
+            weights: vec![vec![vec![0.0; filter_length]; num_channels]; num_channels],
+            history: vec![vec![0.0; 2 * filter_length]; num_channels],
+            history_pos: 0,
+            history_energy: vec![0.0; num_channels],
+            level: vec![0.0; num_channels],
+            residual_level: vec![0.0; num_channels],
+            errors: vec![0.0; num_channels],
+        }
+    }
 
-    // Apply a stronger reduction factor (squared to make it more aggressive)
-    let enhanced_reduction = reduction * 2.0;
-    
-    for sample_idx in 0..samples_per_channel {
-        // Process each channel
-        for (ch_idx, channel) in channel_data.iter().enumerate() {
-            let main_signal = channel[sample_idx];
-            let mut filtered_signal = main_signal;
-            
-            // Apply crosstalk reduction from other channels
-            for (other_idx, other_channel) in channel_data.iter().enumerate() {
-                if other_idx != ch_idx {
-                    let other_signal = other_channel[sample_idx];
-                    
-                    // Calculate correlation between signals at this point
-                    let correlation = (main_signal * other_signal).abs();
-                    
-                    // Normalize by channel energy to get relative correlation
-                    let normalized_correlation = if channel_energy[ch_idx] > 1e-6 && channel_energy[other_idx] > 1e-6 {
-                        correlation / (channel_energy[ch_idx] * channel_energy[other_idx])
-                    } else {
-                        0.0
-                    };
-                    
-                    // Apply stronger reduction with a non-linear response curve
-                    if normalized_correlation > threshold {
-                        // Apply non-linear scaling to make reduction more aggressive
-                        let scale_factor = (normalized_correlation - threshold) / (1.0 - threshold);
-                        let dynamic_reduction = enhanced_reduction * (1.0 + scale_factor);
-                        
-                        // Limit maximum reduction to 1.0 to avoid over-subtraction
-                        let clamped_reduction = dynamic_reduction.min(1.0);
-                        filtered_signal -= other_signal * clamped_reduction;
+    /// Filter length actually used for `num_channels`, within `MAX_CANCELLER_WEIGHTS`.
+    pub fn capped_length(num_channels: usize, filter_length: usize) -> usize {
+        let max_length = MAX_CANCELLER_WEIGHTS / (num_channels * num_channels).max(1);
+        filter_length.min(max_length).max(1)
+    }
+
+    /// Cancels crosstalk in place on interleaved samples.
+    pub fn process(&mut self, samples: &mut [f32], step_size: f32, dominance_db: f32, adapt: bool) {
+        let num_channels = self.num_channels;
+        if num_channels <= 1 {
+            return;
+        }
+        let taps = self.filter_length;
+        let dominance = 10f32.powf(dominance_db.max(0.0) / 10.0);
+
+        for frame in samples.chunks_exact_mut(num_channels) {
+            // Push the new input samples into each channel's history
+            self.history_pos = if self.history_pos == 0 { taps - 1 } else { self.history_pos - 1 };
+            let pos = self.history_pos;
+            for (ch, &sample) in frame.iter().enumerate() {
+                let oldest = self.history[ch][pos];
+                self.history_energy[ch] = (self.history_energy[ch] + sample * sample - oldest * oldest).max(0.0);
+                self.history[ch][pos] = sample;
+                self.history[ch][pos + taps] = sample;
+                follow_level(&mut self.level[ch], sample * sample);
+            }
+
+            // Predict each channel's bleed from the other channels' histories
+            for (dest, error) in self.errors.iter_mut().enumerate() {
+                let mut predicted = 0.0f32;
+                for source in (0..num_channels).filter(|&s| s != dest) {
+                    let history = &self.history[source][pos..pos + taps];
+                    predicted += self.weights[dest][source].iter().zip(history).map(|(w, h)| w * h).sum::<f32>();
+                }
+                *error = frame[dest] - predicted;
+                follow_level(&mut self.residual_level[dest], *error * *error);
+            }
+
+            if adapt {
+                for dest in 0..num_channels {
+                    let (source_level, source_energy) = (0..num_channels)
+                        .filter(|&s| s != dest)
+                        .fold((0.0f32, 0.0f32), |(level, energy), s| (level + self.level[s], energy + self.history_energy[s]));
+                    // Full step while the sources stand well above the residual, falling
+                    // off smoothly as the channel's own sound takes over
+                    let ratio = source_level / (self.residual_level[dest] * dominance + 1e-12);
+                    let control = ratio * ratio / (1.0 + ratio * ratio);
+                    let mu = step_size * control * self.errors[dest] / (source_energy + 1e-6);
+                    for source in (0..num_channels).filter(|&s| s != dest) {
+                        let history = &self.history[source][pos..pos + taps];
+                        for (w, &h) in self.weights[dest][source].iter_mut().zip(history) {
+                            *w += mu * h;
+                        }
                     }
                 }
             }
-            
-            filtered[ch_idx][sample_idx] = filtered_signal;
+
+            frame.copy_from_slice(&self.errors);
+        }
+    }
+}
+
+/// Power envelope with a fast attack and slow release.
+fn follow_level(level: &mut f32, power: f32) {
+    let time = if power > *level { DOMINANCE_ATTACK_SAMPLES } else { DOMINANCE_RELEASE_SAMPLES };
+    *level += (power - *level) / time;
+}
+
+/// Hands cancellers built on another thread to the audio callback, and takes back the
+/// ones it replaces, so the callback never allocates or frees one.
+#[derive(Default)]
+pub struct CancellerHandoff {
+    incoming: Option<CrosstalkCanceller>,
+    retired: Option<CrosstalkCanceller>,
+    // Channels and filter length of the canceller last handed over, None while off
+    built: Option<(usize, usize)>,
+}
+
+impl CancellerHandoff {
+    /// Builds a fresh canceller when cancellation is switched on or resized, and frees
+    /// any the callback gave back. Not for the audio thread.
+    pub fn prepare(&mut self, config: &AdaptiveCrosstalkConfig, num_channels: usize) {
+        self.retired = None;
+        let wanted = (config.enabled && num_channels > 1)
+            .then(|| (num_channels, CrosstalkCanceller::capped_length(num_channels, config.filter_length)));
+        if wanted != self.built {
+            self.incoming = wanted.map(|(channels, length)| CrosstalkCanceller::new(channels, length));
+            self.built = wanted;
         }
     }
 
-    filtered
+    /// Swaps a waiting canceller into `current`, or takes `current` back once cancellation
+    /// is off so it starts from scratch the next time. Doesn't allocate or free.
+    pub fn exchange(&mut self, current: &mut Option<CrosstalkCanceller>, enabled: bool) {
+        if self.retired.is_some() {
+            return;
+        }
+        if !enabled {
+            if current.is_some() {
+                self.retired = current.take();
+                self.built = None;
+            }
+        } else if let Some(canceller) = self.incoming.take() {
+            self.retired = current.replace(canceller);
+        }
+    }
 }
 
 /// Check if all channels have nearly identical signals
@@ -561,12 +1017,13 @@ pub fn compute_spectrum(
 
     // 3. First collect all valid magnitudes above threshold
     let freq_step = sample_rate as f32 / signal.len() as f32;
+    let full_scale = full_scale_magnitude(signal.len(), config.window_type);
     let mut all_magnitudes: Vec<(f32, f32)> = spectrum
         .par_iter()
         .enumerate()
         .filter_map(|(i, &complex_val)| {
             let frequency = i as f32 * freq_step;
-            let magnitude = (complex_val.re * complex_val.re + complex_val.im * complex_val.im).sqrt();
+            let magnitude = (complex_val.re * complex_val.re + complex_val.im * complex_val.im).sqrt() / full_scale;
             
             // Only compute dB if magnitude is significant
             if magnitude > 1e-10 { // Use a small epsilon to avoid log(0)
@@ -630,16 +1087,27 @@ pub enum WindowType {
     Kaiser(f32), // Adjustable side-lobe level, beta parameter
 }
 
-pub fn apply_window(signal: &[f32], window_type: WindowType) -> Vec<f32> {
-    let len = signal.len();
-    let window = match window_type {
+fn window_coefficients(len: usize, window_type: WindowType) -> Vec<f32> {
+    match window_type {
         WindowType::Rectangular => vec![1.0; len],
         WindowType::Hanning => hanning_window(len),
         WindowType::Hamming => hamming_window(len),
         WindowType::BlackmanHarris => blackman_harris_window(len),
         WindowType::FlatTop => flattop_window(len),
         WindowType::Kaiser(beta) => kaiser_window(len, beta),
-    };
+    }
+}
+
+/// Raw FFT magnitude a full-scale sine reads at this FFT length and window: the peak
+/// bin is |X| = sum(w) / 2. Dividing by it makes magnitudes read in dBFS.
+fn full_scale_magnitude(len: usize, window_type: WindowType) -> f32 {
+    let coherent_sum: f32 = window_coefficients(len, window_type).iter().sum();
+    (coherent_sum / 2.0).max(1e-10)
+}
+
+pub fn apply_window(signal: &[f32], window_type: WindowType) -> Vec<f32> {
+    let len = signal.len();
+    let window = window_coefficients(len, window_type);
     
     signal.iter()
         .zip(window.iter())
@@ -719,8 +1187,9 @@ pub fn filter_crosstalk_frequency_domain(
     root_freq_min: f32,
     mut root_freq_max: f32,
     freq_match_distance: f32,
-    sample_rate: u32
-) -> Vec<Vec<(f32, f32)>> {
+    sample_rate: u32,
+    pitch_roots: &[Option<f32>],
+) -> (Vec<Vec<(f32, f32)>>, CrosstalkReport) {
     // Instead of using sample_rate:
     let nyquist = (sample_rate as f32 / 2.0).min(8192.0);
 
@@ -733,18 +1202,24 @@ pub fn filter_crosstalk_frequency_domain(
                     spectra.len(), threshold, reduction);
 
     if spectra.is_empty() {
-        return Vec::new();
+        return (Vec::new(), CrosstalkReport::default());
     }
 
     let num_channels = spectra.len();
     if num_channels == 1 {
-        return spectra.clone(); // No crosstalk with single channel
+        return (spectra.clone(), CrosstalkReport::default()); // No crosstalk with single channel
     }
 
     // Continue crosstalk logic as before, using the newly clamped `root_freq_max`:
     // e.g. finding root in range [root_freq_min .. root_freq_max]
     let mut root_frequencies: Vec<f32> = Vec::with_capacity(num_channels);
     for (ch_idx, channel_spectra) in spectra.iter().enumerate() {
+        // A confident pitch estimate wins over the loudest partial in range
+        if let Some(&Some(f0)) = pitch_roots.get(ch_idx) {
+            crosstalk_info!(" Channel {} root freq = {:.2} Hz (pitch estimate)", ch_idx, f0);
+            root_frequencies.push(f0);
+            continue;
+        }
         let root = channel_spectra.iter()
             .filter(|&&(freq, _)| freq > root_freq_min && freq < root_freq_max)
             .max_by(|&&(_, mag_a), &&(_, mag_b)|
@@ -765,10 +1240,16 @@ pub fn filter_crosstalk_frequency_domain(
         }
     }
 
-    let mut filtered_spectra = spectra.clone();
+    // The rules scale and compare linear amplitudes, so work on those and return dBFS
+    let mut filtered_spectra: Vec<Vec<(f32, f32)>> = spectra.iter()
+        .map(|channel| channel.iter()
+            .map(|&(freq, db)| (freq, if freq > 0.0 { 10.0_f32.powf(db / 20.0) } else { 0.0 }))
+            .collect())
+        .collect();
     let scaled_reduction = reduction;
 
     let mut count_filtered = 0;
+    let mut decisions: Vec<CrosstalkDecision> = Vec::new();
     
     // For each frequency in each channel
     for ch_idx in 0..num_channels {
@@ -807,10 +1288,18 @@ pub fn filter_crosstalk_frequency_domain(
                         if other_mag > magnitude * 1.5 {
                             filtered_spectra[ch_idx][i].1 *= 0.1;
                             count_filtered += 1;
+                            decisions.push(CrosstalkDecision {
+                                channel: ch_idx, freq, matched: Some((other_ch, other_freq)),
+                                rule: CrosstalkRule::BothHarmonic, before: magnitude, after: filtered_spectra[ch_idx][i].1,
+                            });
                             crosstalk_info!("  → ch{} weaker harmonic freq={:.1}, reducing 90%", ch_idx, freq);
                         } else if magnitude > other_mag * 1.5 {
                             filtered_spectra[other_ch][other_idx].1 *= 0.1;
                             count_filtered += 1;
+                            decisions.push(CrosstalkDecision {
+                                channel: other_ch, freq: other_freq, matched: Some((ch_idx, freq)),
+                                rule: CrosstalkRule::BothHarmonic, before: other_mag, after: filtered_spectra[other_ch][other_idx].1,
+                            });
                             crosstalk_info!("  → ch{} weaker harmonic freq={:.1}, reducing 90%", other_ch, other_freq);
                         } else {
                             // Similar strength - reduce both proportionally
@@ -820,16 +1309,32 @@ pub fn filter_crosstalk_frequency_domain(
                             filtered_spectra[ch_idx][i].1 *= my_ratio;
                             filtered_spectra[other_ch][other_idx].1 *= other_ratio;
                             count_filtered += 2;
+                            decisions.push(CrosstalkDecision {
+                                channel: ch_idx, freq, matched: Some((other_ch, other_freq)),
+                                rule: CrosstalkRule::BothHarmonic, before: magnitude, after: filtered_spectra[ch_idx][i].1,
+                            });
+                            decisions.push(CrosstalkDecision {
+                                channel: other_ch, freq: other_freq, matched: Some((ch_idx, freq)),
+                                rule: CrosstalkRule::BothHarmonic, before: other_mag, after: filtered_spectra[other_ch][other_idx].1,
+                            });
                             crosstalk_info!("  → Both harmonics freq={:.1} ~ freq={:.1}, proportionally reduced", freq, other_freq);
                         }
                     }
                     else if is_harmonic && !other_is_harmonic {
                         filtered_spectra[other_ch][other_idx].1 *= 1.0 - scaled_reduction;
+                        decisions.push(CrosstalkDecision {
+                            channel: other_ch, freq: other_freq, matched: Some((ch_idx, freq)),
+                            rule: CrosstalkRule::OneHarmonic, before: other_mag, after: filtered_spectra[other_ch][other_idx].1,
+                        });
                         crosstalk_info!("  → Reduced ch{} freq={:.1} (NON-harm), mag now={:.3}", other_ch, other_freq, filtered_spectra[other_ch][other_idx].1);
                         count_filtered += 1;
                     }
                     else if !is_harmonic && other_is_harmonic {
                         filtered_spectra[ch_idx][i].1 *= 1.0 - scaled_reduction;
+                        decisions.push(CrosstalkDecision {
+                            channel: ch_idx, freq, matched: Some((other_ch, other_freq)),
+                            rule: CrosstalkRule::OneHarmonic, before: magnitude, after: filtered_spectra[ch_idx][i].1,
+                        });
                         crosstalk_info!("  → Reduced ch{} freq={:.1} (NON-harm), mag now={:.3}", ch_idx, freq, filtered_spectra[ch_idx][i].1);
                         count_filtered += 1;
                     }
@@ -838,10 +1343,18 @@ pub fn filter_crosstalk_frequency_domain(
                         if other_mag > magnitude * (1.0 + threshold) {
                             filtered_spectra[ch_idx][i].1 *= 1.0 - scaled_reduction;
                             count_filtered += 1;
+                            decisions.push(CrosstalkDecision {
+                                channel: ch_idx, freq, matched: Some((other_ch, other_freq)),
+                                rule: CrosstalkRule::MagnitudeCompare, before: magnitude, after: filtered_spectra[ch_idx][i].1,
+                            });
                             crosstalk_info!("  → ch{} significantly weaker freq={:.1}, mag now={:.3}", ch_idx, freq, filtered_spectra[ch_idx][i].1);
                         } else if magnitude > other_mag * (1.0 + threshold) {
                             filtered_spectra[other_ch][other_idx].1 *= 1.0 - scaled_reduction;
                             count_filtered += 1;
+                            decisions.push(CrosstalkDecision {
+                                channel: other_ch, freq: other_freq, matched: Some((ch_idx, freq)),
+                                rule: CrosstalkRule::MagnitudeCompare, before: other_mag, after: filtered_spectra[other_ch][other_idx].1,
+                            });
                             crosstalk_info!("  → ch{} significantly weaker freq={:.1}, mag now={:.3}", other_ch, other_freq, filtered_spectra[other_ch][other_idx].1);
                         }
                     }
@@ -862,8 +1375,95 @@ pub fn filter_crosstalk_frequency_domain(
     }
 
     crosstalk_info!("Crosstalk filter applied - filtered {} frequencies", count_filtered);
-    
-    filtered_spectra
+
+    for decision in decisions.iter_mut() {
+        decision.before = amplitude_to_dbfs(decision.before);
+        decision.after = amplitude_to_dbfs(decision.after);
+    }
+    for partial in filtered_spectra.iter_mut().flatten() {
+        // A partial reduced to nothing becomes an empty slot
+        *partial = if partial.1 > 1e-10 { (partial.0, amplitude_to_dbfs(partial.1)) } else { (0.0, 0.0) };
+    }
+
+    (filtered_spectra, CrosstalkReport { roots: root_frequencies, decisions })
+}
+
+fn amplitude_to_dbfs(amplitude: f32) -> f32 {
+    if amplitude > 1e-10 { 20.0 * amplitude.log10() } else { LINE_FLOOR_DB }
+}
+
+/// Subtracts the leakage predicted by a measured crosstalk matrix from each channel's partials.
+///
+/// The leakage into a channel at a given frequency is estimated from every other
+/// channel's line spectrum at that frequency, weighted by the measured bleed ratio.
+pub fn filter_crosstalk_calibrated(
+    spectra: &[Vec<(f32, f32)>],
+    line_data: &[Vec<(f32, f32)>],
+    matrix: &CrosstalkMatrix,
+    reduction: f32,
+    config: &FFTConfig,
+) -> (Vec<Vec<(f32, f32)>>, CrosstalkReport) {
+    let num_channels = spectra.len();
+    if num_channels <= 1 || line_data.len() != num_channels {
+        return (spectra.to_vec(), CrosstalkReport::default());
+    }
+
+    let mut decisions: Vec<CrosstalkDecision> = Vec::new();
+    let mut filtered: Vec<Vec<(f32, f32)>> = Vec::with_capacity(num_channels);
+    for (dest, channel_partials) in spectra.iter().enumerate() {
+        let dest_gain = config.for_channel(dest).gain;
+        let channel_filtered = channel_partials.iter().map(|&(freq, db)| {
+            if freq <= 0.0 {
+                return (freq, db);
+            }
+            // Leakage contributed by each other channel, remembering the largest source
+            let mut predicted = 0.0f32;
+            let mut strongest: Option<(usize, f32)> = None;
+            for source in (0..num_channels).filter(|&source| source != dest) {
+                let source_db = line_magnitude_at(&line_data[source], freq);
+                let leakage = 10.0_f32.powf(source_db / 20.0) * matrix.leakage_at(source, dest, freq) * dest_gain;
+                if leakage > strongest.map(|(_, l)| l).unwrap_or(0.0) {
+                    strongest = Some((source, leakage));
+                }
+                predicted += leakage;
+            }
+
+            let magnitude = 10.0_f32.powf(db / 20.0);
+            let remaining = magnitude - reduction * predicted;
+            // A partial with nothing left becomes an empty slot
+            let filtered = if remaining > 1e-10 { (freq, 20.0 * remaining.log10()) } else { (0.0, 0.0) };
+            if predicted > 0.0 {
+                crosstalk_info!("  ch{} freq={:.1}: {:.2} dB, predicted leakage {:.2} dB",
+                                dest, freq, db, 20.0 * predicted.max(1e-10).log10());
+                decisions.push(CrosstalkDecision {
+                    channel: dest,
+                    freq,
+                    matched: strongest.map(|(source, _)| (source, freq)),
+                    rule: CrosstalkRule::Calibrated,
+                    before: db,
+                    after: if filtered.0 > 0.0 { filtered.1 } else { LINE_FLOOR_DB },
+                });
+            }
+            filtered
+        }).collect();
+        filtered.push(channel_filtered);
+    }
+
+    crosstalk_info!("Calibrated crosstalk filter applied - {} partials had predicted leakage", decisions.len());
+    (filtered, CrosstalkReport { roots: vec![0.0; num_channels], decisions })
+}
+
+/// Looks up the dB value of a line spectrum at the bin nearest to `freq`.
+fn line_magnitude_at(line: &[(f32, f32)], freq: f32) -> f32 {
+    if line.len() < 2 {
+        return LINE_FLOOR_DB;
+    }
+    let freq_step = line[1].0 - line[0].0;
+    if freq_step <= 0.0 {
+        return LINE_FLOOR_DB;
+    }
+    let index = ((freq - line[0].0) / freq_step).round().max(0.0) as usize;
+    line.get(index).map(|&(_, db)| db).unwrap_or(LINE_FLOOR_DB)
 }
 
 /// Helper function to check if a frequency is a harmonic of a root frequency
@@ -904,7 +1504,7 @@ fn find_closest_frequency(spectrum: &[(f32, f32)], target: f32, max_distance: f3
 
 /// Extracts partials (frequency, magnitude peaks) from a pre-computed complex FFT spectrum.
 fn extract_partials_from_spectrum(
-    spectrum: &[Complex<f32>],
+    spectrum: &[f32],
     sample_rate: u32,
     signal_len: usize, // Need original signal length for freq_step
     config: &FFTConfig,
@@ -912,16 +1512,16 @@ fn extract_partials_from_spectrum(
     // 1. Calculate frequency step
     let freq_step = sample_rate as f32 / signal_len as f32;
 
-    // Convert dB threshold to linear magnitude threshold once
+    // Convert the dBFS threshold to a linear magnitude threshold once
     let linear_magnitude_threshold = 10.0_f32.powf(config.magnitude_threshold as f32 / 20.0);
 
     // 2. Collect all valid magnitudes above threshold from the complex spectrum
     let mut all_magnitudes: Vec<(f32, f32)> = spectrum
         .par_iter()
         .enumerate()
-        .filter_map(|(i, &complex_val)| {
+        .filter_map(|(i, &magnitude)| {
             let frequency = i as f32 * freq_step;
-            let mut magnitude = (complex_val.re * complex_val.re + complex_val.im * complex_val.im).sqrt();
+            let mut magnitude = magnitude;
             
             // Apply gain before threshold check
             magnitude *= config.gain;
@@ -934,7 +1534,7 @@ fn extract_partials_from_spectrum(
                 let db = if magnitude > 1e-10 { // Use an epsilon for stability
                     20.0 * magnitude.log10()
                 } else {
-                    -120.0 // Use a large negative number for silence, as is standard
+                    LINE_FLOOR_DB // Use a large negative number for silence, as is standard
                 };
                 Some((frequency, db)) // Return correct dB magnitude
             } else {
@@ -978,3 +1578,96 @@ fn extract_partials_from_spectrum(
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic white noise in -0.5..0.5.
+    struct Noise(u32);
+
+    impl Noise {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+            (self.0 >> 8) as f32 / 16_777_216.0 - 0.5
+        }
+    }
+
+    // Bleed path from channel 0 into channel 1: a short FIR with about -6 dB of gain
+    const BLEED_PATH: [f32; 5] = [0.0, 0.4, 0.25, -0.15, 0.1];
+
+    /// Runs `samples` frames of noise on channel 0, bleeding into channel 1 on top of its
+    /// own noise at `own_gain`, and returns the bleed left in channel 1 over the second
+    /// half, in dB relative to the bleed.
+    fn residual_bleed_db(canceller: &mut CrosstalkCanceller, seed: u32, own_gain: f32, samples: usize) -> f32 {
+        let config = AdaptiveCrosstalkConfig::default();
+        let (mut source, mut own) = (Noise(seed), Noise(seed.wrapping_add(1)));
+        let mut history = [0.0f32; BLEED_PATH.len()];
+        let (mut residual, mut bleed_power) = (0.0f64, 0.0f64);
+        for i in 0..samples {
+            history.rotate_right(1);
+            history[0] = source.next();
+            let bleed: f32 = BLEED_PATH.iter().zip(&history).map(|(p, h)| p * h).sum();
+            let own_sample = own.next() * own_gain;
+            let mut frame = [history[0], own_sample + bleed];
+            canceller.process(&mut frame, config.step_size, config.dominance_db, true);
+            if i >= samples / 2 {
+                residual += ((frame[1] - own_sample) as f64).powi(2);
+                bleed_power += (bleed as f64).powi(2);
+            }
+        }
+        (10.0 * (residual / bleed_power).log10()) as f32
+    }
+
+    #[test]
+    fn canceller_converges_on_convolved_bleed() {
+        let mut canceller = CrosstalkCanceller::new(2, AdaptiveCrosstalkConfig::default().filter_length);
+        assert!(residual_bleed_db(&mut canceller, 1, 0.001, 96_000) < -40.0);
+    }
+
+    #[test]
+    fn canceller_keeps_its_paths_through_double_talk() {
+        let mut canceller = CrosstalkCanceller::new(2, AdaptiveCrosstalkConfig::default().filter_length);
+        residual_bleed_db(&mut canceller, 1, 0.001, 96_000);
+        // Channel 1's own sound as loud as the source: adaptation slows instead of
+        // learning it, and the learnt path comes straight back afterwards
+        assert!(residual_bleed_db(&mut canceller, 2, 1.0, 96_000) < -15.0);
+        assert!(residual_bleed_db(&mut canceller, 3, 0.001, 48_000) < -40.0);
+    }
+
+    #[test]
+    fn canceller_length_is_capped_by_channel_count() {
+        assert_eq!(CrosstalkCanceller::capped_length(2, 256), 256);
+        assert_eq!(CrosstalkCanceller::capped_length(8, 256), MAX_CANCELLER_WEIGHTS / 64);
+        assert_eq!(CrosstalkCanceller::capped_length(2, 0), 1);
+    }
+
+    #[test]
+    fn full_scale_sine_reads_0_dbfs() {
+        let windows = [
+            WindowType::Rectangular,
+            WindowType::Hanning,
+            WindowType::Hamming,
+            WindowType::BlackmanHarris,
+            WindowType::FlatTop,
+            WindowType::Kaiser(8.0),
+        ];
+        for window_type in windows {
+            for len in [1024, 4096, 8192] {
+                // A bin-centred sine at full scale
+                let bin = len / 8;
+                let signal: Vec<f32> = (0..len)
+                    .map(|n| (2.0 * std::f32::consts::PI * (bin * n) as f32 / len as f32).sin())
+                    .collect();
+                let config = FFTConfig { window_type, ..FFTConfig::default() };
+                let (partials, line_data, _) = compute_all_fft_data(
+                    &[signal], 0, 48_000, &config, &mut SpectrumAverager::default(), 0.0,
+                );
+                let peak = line_data.iter().map(|&(_, db)| db).fold(f32::NEG_INFINITY, f32::max);
+                assert!(peak.abs() < 0.1, "{:?} at {} samples peaks at {} dBFS", window_type, len, peak);
+                let loudest = partials.iter().map(|&(_, db)| db).fold(f32::NEG_INFINITY, f32::max);
+                assert!(loudest.abs() < 0.1, "{:?} at {} samples has its loudest partial at {} dBFS", window_type, len, loudest);
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+// Seconds a frozen layer takes to fade in or out, and the live path to make way for it
+const LAYER_FADE_SECONDS: f32 = 0.5;
+
+/// A sine oscillator advanced by rotating a phasor, so each sample costs a complex
+/// multiply instead of a `sin` call.
+struct Oscillator {
+    output: usize,
+    amp: f32,
+    re: f32,
+    im: f32,
+    step_re: f32,
+    step_im: f32,
+}
+
+/// One latched set of partials, sounding until it is released.
+struct FrozenLayer {
+    id: u32,
+    oscillators: Vec<Oscillator>,
+    level: f32,
+    fade: f32,
+    releasing: bool,
+}
+
+/// Frozen snapshots of the resynthesised partials, layered on top of the live segments
+/// for as long as they are held, independent of segment length and update rate.
+pub struct FreezeBank {
+    layers: Vec<FrozenLayer>,
+    fade_step: f32,
+    mute_live: bool,
+    live_weight: f32,
+}
+
+impl FreezeBank {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            layers: Vec::new(),
+            fade_step: 1.0 / (LAYER_FADE_SECONDS * sample_rate).max(1.0),
+            mute_live: false,
+            live_weight: 1.0,
+        }
+    }
+
+    /// Latches partials per output channel as (Hz, amplitude) into a new layer that fades in.
+    pub fn freeze(&mut self, id: u32, outputs: &[Vec<(f32, f32)>], sample_rate: f32) {
+        self.fade_step = 1.0 / (LAYER_FADE_SECONDS * sample_rate).max(1.0);
+        let nyquist = sample_rate / 2.0;
+        let oscillators = outputs.iter()
+            .enumerate()
+            .flat_map(|(output, partials)| partials.iter()
+                .filter(move |&&(freq, amp)| freq > 0.0 && freq < nyquist && amp > 0.0)
+                .map(move |&(freq, amp)| {
+                    let step = std::f32::consts::TAU * freq / sample_rate;
+                    Oscillator { output, amp, re: 1.0, im: 0.0, step_re: step.cos(), step_im: step.sin() }
+                }))
+            .collect();
+        self.layers.push(FrozenLayer { id, oscillators, level: 1.0, fade: 0.0, releasing: false });
+    }
+
+    /// Fades a layer out; it is dropped once silent.
+    pub fn release(&mut self, id: u32) {
+        self.layers.iter_mut().filter(|layer| layer.id == id).for_each(|layer| layer.releasing = true);
+    }
+
+    pub fn set_level(&mut self, id: u32, level: f32) {
+        self.layers.iter_mut().filter(|layer| layer.id == id).for_each(|layer| layer.level = level);
+    }
+
+    /// Fade the live resynthesis out while any layer is held.
+    pub fn set_mute_live(&mut self, mute_live: bool) {
+        self.mute_live = mute_live;
+    }
+
+    /// Weight for this frame's live samples, ramping towards silence while muted for a
+    /// held layer and back to 1 otherwise.
+    pub fn next_live_weight(&mut self) -> f32 {
+        let holding = self.layers.iter().any(|layer| !layer.releasing);
+        let target = if self.mute_live && holding { 0.0 } else { 1.0 };
+        self.live_weight = if self.live_weight < target {
+            (self.live_weight + self.fade_step).min(target)
+        } else {
+            (self.live_weight - self.fade_step).max(target)
+        };
+        self.live_weight
+    }
+
+    /// Adds one frame of every layer into an interleaved output frame.
+    pub fn add_frame(&mut self, frame: &mut [f32]) {
+        for layer in self.layers.iter_mut() {
+            layer.fade = if layer.releasing {
+                (layer.fade - self.fade_step).max(0.0)
+            } else {
+                (layer.fade + self.fade_step).min(1.0)
+            };
+            let weight = layer.fade * layer.level;
+            for osc in layer.oscillators.iter_mut() {
+                if let Some(sample) = frame.get_mut(osc.output) {
+                    *sample += osc.amp * osc.im * weight;
+                }
+                let re = osc.re * osc.step_re - osc.im * osc.step_im;
+                osc.im = osc.re * osc.step_im + osc.im * osc.step_re;
+                osc.re = re;
+            }
+        }
+    }
+
+    /// Drops layers that have faded out and pulls every phasor back to unit length, which
+    /// rounding drifts away from over many rotations. Called once per buffer.
+    pub fn end_buffer(&mut self) {
+        self.layers.retain(|layer| !(layer.releasing && layer.fade <= 0.0));
+        for osc in self.layers.iter_mut().flat_map(|layer| layer.oscillators.iter_mut()) {
+            let magnitude = (osc.re * osc.re + osc.im * osc.im).sqrt();
+            if magnitude > 0.0 {
+                osc.re /= magnitude;
+                osc.im /= magnitude;
+            }
+        }
+    }
+}
@@ -5,16 +5,34 @@ use std::time::{Duration, Instant};
 use log::{debug, warn, error};
 use crate::ResynthConfig;
 use crate::resynth::SynthUpdate;
+use crate::routing::OutputRouting;
+use crate::transforms::PartialTransform;
+use crate::limiter::LimiterConfig;
+use crate::tuning::Tuning;
 use tokio::sync::broadcast;
 
 // Define type alias
 type PartialsData = Vec<Vec<(f32, f32)>>;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum GuiParameter {
     Gain(f32),
     FreqScale(f32),
     UpdateRate(f32),
+    ChannelMask(Vec<bool>),  // Channels to resynthesise; muted channels are silenced
+    Routing(OutputRouting),  // Output channel count and input to output gains
+    NoiseLevel(f32),         // Level of the resynthesised residual noise
+    TransposeSemitones(f32), // Pitch shift in semitones
+    TransposeCents(f32),     // Fine pitch shift in cents
+    PreserveFormants(bool),  // Keep the spectral envelope in place when shifting pitch
+    Transforms(Vec<PartialTransform>), // Chain applied to the partials before synthesis
+    Tuning(Tuning),          // Scale the Quantize transform snaps to, already resolved
+    FreezeLayer(u32),        // Latch the current partials into a held layer with this ID
+    ReleaseLayer(u32),       // Fade a held layer out
+    LayerLevel(u32, f32),    // Level of a held layer
+    MuteLiveWhileFrozen(bool), // Fade the live resynthesis out while layers are held
+    LowLatency(bool),        // Drive the oscillators from every analysis frame
+    Limiter(LimiterConfig),  // Safety stage at the end of the output path
 }
 
 // The old start_update_thread function that used ArrayQueue and ResynthConfig.snapshot()
@@ -38,6 +56,11 @@ pub fn start_update_thread_with_sender(
         let mut local_gain = initial_config_guard.gain;
         let mut local_freq_scale = initial_config_guard.freq_scale;
         let mut local_update_rate = initial_config_guard.update_rate;
+        let mut local_routing = initial_config_guard.routing.clone();
+        let mut local_noise_level = initial_config_guard.noise_level;
+        let mut local_transpose_semitones = initial_config_guard.transpose_semitones;
+        let mut local_transpose_cents = initial_config_guard.transpose_cents;
+        let mut local_preserve_formants = initial_config_guard.preserve_formants;
         drop(initial_config_guard);
         debug!(target: "get_results", "Initial local state: Gain={:.2}, FreqScale={:.2}, UpdateRate={:.3}s",
                local_gain, local_freq_scale, local_update_rate);
@@ -83,6 +106,52 @@ pub fn start_update_thread_with_sender(
                         // gui_event_occurred = true; // An update rate change doesn't need to force using cached partials
                     }
                 }
+                Ok(GuiParameter::Routing(routing)) => {
+                    if routing != local_routing {
+                        debug!(target: "get_results", "Event: GUI Routing changed: {} outputs", routing.num_outputs);
+                        local_routing = routing;
+                        immediate_send_triggered_this_cycle = true;
+                        gui_event_occurred = true;
+                    }
+                }
+                Ok(GuiParameter::NoiseLevel(level)) => {
+                    if (level - local_noise_level).abs() > 1e-6 {
+                        debug!(target: "get_results", "Event: GUI NoiseLevel changed: {:.2} -> {:.2}", local_noise_level, level);
+                        local_noise_level = level;
+                        immediate_send_triggered_this_cycle = true;
+                        gui_event_occurred = true;
+                    }
+                }
+                Ok(GuiParameter::TransposeSemitones(semitones)) => {
+                    if (semitones - local_transpose_semitones).abs() > 1e-6 {
+                        debug!(target: "get_results", "Event: GUI TransposeSemitones changed: {:.2} -> {:.2}", local_transpose_semitones, semitones);
+                        local_transpose_semitones = semitones;
+                        immediate_send_triggered_this_cycle = true;
+                        gui_event_occurred = true;
+                    }
+                }
+                Ok(GuiParameter::TransposeCents(cents)) => {
+                    if (cents - local_transpose_cents).abs() > 1e-6 {
+                        debug!(target: "get_results", "Event: GUI TransposeCents changed: {:.2} -> {:.2}", local_transpose_cents, cents);
+                        local_transpose_cents = cents;
+                        immediate_send_triggered_this_cycle = true;
+                        gui_event_occurred = true;
+                    }
+                }
+                Ok(GuiParameter::PreserveFormants(enabled)) => {
+                    if enabled != local_preserve_formants {
+                        debug!(target: "get_results", "Event: GUI PreserveFormants changed: {}", enabled);
+                        local_preserve_formants = enabled;
+                        immediate_send_triggered_this_cycle = true;
+                        gui_event_occurred = true;
+                    }
+                }
+                Ok(GuiParameter::ChannelMask(_)) | Ok(GuiParameter::Transforms(_)) | Ok(GuiParameter::Tuning(_))
+                | Ok(GuiParameter::FreezeLayer(_)) | Ok(GuiParameter::ReleaseLayer(_))
+                | Ok(GuiParameter::LayerLevel(..)) | Ok(GuiParameter::MuteLiveWhileFrozen(_))
+                | Ok(GuiParameter::LowLatency(_)) | Ok(GuiParameter::Limiter(_)) => {
+                    // Channel muting, partial transforms, frozen layers, low latency and the limiter are handled by the resynth thread's own update loop
+                }
                 Err(mpsc::RecvTimeoutError::Timeout) => {
                     // This is expected. Proceed to check other conditions.
                 }
@@ -155,6 +224,13 @@ pub fn start_update_thread_with_sender(
                         gain: local_gain,
                         freq_scale: local_freq_scale,
                         update_rate: local_update_rate, // Send the current *effective* update rate
+                        onset: false,
+                        routing: local_routing.clone(),
+                        tags: Vec::new(),
+                        noise: Vec::new(),
+                        noise_level: local_noise_level,
+                        transpose: local_transpose_semitones + local_transpose_cents / 100.0,
+                        preserve_formants: local_preserve_formants,
                     };
                     debug!(target: "get_results", 
                            "Update #{}: Sending SynthUpdate. Gain={:.2}, FScale={:.2}, URate={:.3}s, Partials_Chans={}", 
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+// Partials further than this from their assumed harmonic are left out of the inharmonicity fit
+const FIT_TOLERANCE_CENTS: f32 = 50.0;
+// Fewest labelled partials needed before an inharmonicity coefficient is reported
+const MIN_FIT_POINTS: usize = 3;
+// Highest harmonic used to seed the fit, where stretching is still small enough to round f/f0
+const SEED_MAX_HARMONIC: u32 = 8;
+// Reassign-and-refit rounds after the seed fit
+const REFINE_PASSES: usize = 3;
+
+/// Harmonic label for one partial.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PartialHarmonic {
+    pub harmonic: u32,
+    /// Deviation from `harmonic * f0` in cents
+    pub cents: f32,
+}
+
+/// Harmonic labels for a channel's partials plus the fitted stiff-string inharmonicity.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HarmonicAnalysis {
+    /// One entry per partial, `None` for empty slots
+    pub partials: Vec<Option<PartialHarmonic>>,
+    /// Inharmonicity coefficient B in f_n = n f0 sqrt(1 + B n^2)
+    pub inharmonicity: Option<f32>,
+}
+
+fn cents(freq: f32, reference: f32) -> f32 {
+    1200.0 * (freq / reference).log2()
+}
+
+/// Harmonic number whose stiff-string frequency is nearest to `freq`.
+fn nearest_harmonic(freq: f32, f0: f32, b: f32) -> u32 {
+    let ratio = freq / f0;
+    let n = if b > 1e-9 {
+        // Solve ratio = n sqrt(1 + B n^2) for n
+        ((-1.0 + (1.0 + 4.0 * b * ratio * ratio).sqrt()) / (2.0 * b)).sqrt()
+    } else {
+        ratio
+    };
+    n.round().max(1.0) as u32
+}
+
+/// Least-squares fit of (f_n / n)^2 = f0^2 + f0^2 B n^2, returning (f0, B).
+fn fit_stiff_string(points: &[(u32, f32)]) -> Option<(f32, f32)> {
+    if points.len() < MIN_FIT_POINTS {
+        return None;
+    }
+    let xs: Vec<f64> = points.iter().map(|&(n, _)| (n as f64).powi(2)).collect();
+    let ys: Vec<f64> = points.iter().map(|&(n, f)| (f as f64 / n as f64).powi(2)).collect();
+    let count = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / count;
+    let mean_y = ys.iter().sum::<f64>() / count;
+    let sxx: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    if sxx <= 0.0 {
+        return None; // Every point has the same harmonic number
+    }
+    let sxy: f64 = xs.iter().zip(&ys).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    if intercept <= 0.0 {
+        return None;
+    }
+    Some((intercept.sqrt() as f32, (slope / intercept) as f32))
+}
+
+/// Labels each partial with its harmonic number relative to `f0`, its deviation in cents
+/// from the ideal harmonic, and fits the inharmonicity coefficient B.
+///
+/// Harmonic numbers of the low partials are assigned by rounding f/f0 to seed the fit;
+/// the rest are then assigned against the stiff-string model so stretched upper partials
+/// keep their number.
+pub fn analyze_harmonics(partials: &[(f32, f32)], f0: f32) -> HarmonicAnalysis {
+    if f0 <= 0.0 {
+        return HarmonicAnalysis { partials: vec![None; partials.len()], inharmonicity: None };
+    }
+    let active: Vec<(usize, f32)> = partials.iter()
+        .enumerate()
+        .filter(|&(_, &(freq, _))| freq > 0.0)
+        .map(|(i, &(freq, _))| (i, freq))
+        .collect();
+
+    // Seed: low partials against the plain harmonic series
+    let seed: Vec<(u32, f32)> = active.iter()
+        .map(|&(_, freq)| (nearest_harmonic(freq, f0, 0.0), freq))
+        .filter(|&(n, freq)| n <= SEED_MAX_HARMONIC && cents(freq, n as f32 * f0).abs() < FIT_TOLERANCE_CENTS)
+        .collect();
+    let mut fit = fit_stiff_string(&seed);
+
+    // Reassign every partial against the fitted model and refit
+    for _ in 0..REFINE_PASSES {
+        let Some((fit_f0, b)) = fit else { break };
+        let b = b.max(0.0);
+        let model = |n: u32| n as f32 * fit_f0 * (1.0 + b * (n * n) as f32).sqrt();
+        let refined: Vec<(u32, f32)> = active.iter()
+            .map(|&(_, freq)| (nearest_harmonic(freq, fit_f0, b), freq))
+            .filter(|&(n, freq)| cents(freq, model(n)).abs() < FIT_TOLERANCE_CENTS)
+            .collect();
+        fit = fit_stiff_string(&refined).or(fit);
+    }
+
+    let b = fit.map(|(_, b)| b);
+    let (label_f0, label_b) = fit.map(|(f, b)| (f, b.max(0.0))).unwrap_or((f0, 0.0));
+    let mut labels = vec![None; partials.len()];
+    for (i, freq) in active {
+        let harmonic = nearest_harmonic(freq, label_f0, label_b);
+        labels[i] = Some(PartialHarmonic {
+            harmonic,
+            cents: cents(freq, harmonic as f32 * f0),
+        });
+    }
+
+    HarmonicAnalysis { partials: labels, inharmonicity: b }
+}
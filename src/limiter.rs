@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// Corner of the DC blocker's high-pass, well below anything audible
+const DC_BLOCK_HZ: f32 = 10.0;
+
+/// Settings for the safety stage at the end of the resynthesis output path.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimiterConfig {
+    pub enabled: bool,       // Look-ahead peak limiting to the ceiling
+    pub ceiling_db: f32,     // dBFS no output sample may exceed
+    pub lookahead_ms: f32,   // How far ahead peaks are seen; also the delay the stage adds
+    pub release_ms: f32,     // Time for the gain to recover by about 63% after a peak
+    pub dc_block: bool,      // High-pass at DC_BLOCK_HZ before limiting
+    pub soft_clip: bool,     // Round peaks off towards the ceiling after limiting
+}
+
+impl Default for LimiterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ceiling_db: -1.0,
+            lookahead_ms: 5.0,
+            release_ms: 100.0,
+            dc_block: true,
+            soft_clip: false,
+        }
+    }
+}
+
+/// Levels of the latest output buffer, for the GUI meters.
+#[derive(Debug, Clone, Default)]
+pub struct OutputMeters {
+    pub peak_db: Vec<f32>,       // Per output channel, after the safety stage
+    pub gain_reduction_db: f32,  // Deepest limiter reduction in the buffer, 0 or negative
+}
+
+/// DC blocker, look-ahead limiter and soft clip over interleaved output frames.
+///
+/// The limiter works out the gain each frame needs to stay under the ceiling, holds the
+/// lowest over the look-ahead window, lets it recover at the release rate, then averages
+/// it over the window again. The audio is delayed by the window, so the gain has fully
+/// come down by the time a peak is played and no sample gets through above the ceiling.
+pub struct OutputStage {
+    config: LimiterConfig,
+    sample_rate: f32,
+    num_outputs: usize,
+    ceiling: f32,
+    dc_coefficient: f32,
+    release_coefficient: f32,
+    lookahead_frames: usize,
+    dc_state: Vec<(f32, f32)>, // Previous input and output per channel
+    delay: VecDeque<f32>,      // Interleaved frames waiting to be played
+    held: VecDeque<(usize, f32)>, // Candidates for the window's lowest gain, oldest first
+    released_gain: f32,
+    smoothing: VecDeque<f32>,
+    smoothing_sum: f64,
+    frame_index: usize,
+    peaks: Vec<f32>, // Per channel over the current buffer
+    meters: Arc<Mutex<OutputMeters>>,
+}
+
+impl OutputStage {
+    pub fn new(config: LimiterConfig, sample_rate: f32, meters: Arc<Mutex<OutputMeters>>) -> Self {
+        let mut stage = Self {
+            config,
+            sample_rate,
+            num_outputs: 0,
+            ceiling: 1.0,
+            dc_coefficient: 0.0,
+            release_coefficient: 0.0,
+            lookahead_frames: 1,
+            dc_state: Vec::new(),
+            delay: VecDeque::new(),
+            held: VecDeque::new(),
+            released_gain: 1.0,
+            smoothing: VecDeque::new(),
+            smoothing_sum: 0.0,
+            frame_index: 0,
+            peaks: Vec::new(),
+            meters,
+        };
+        stage.set_config(config, sample_rate);
+        stage
+    }
+
+    /// Applies new settings. Changing the look-ahead or sample rate clears the delay line.
+    /// Not for the audio thread: a new look-ahead reallocates the buffers.
+    pub fn set_config(&mut self, config: LimiterConfig, sample_rate: f32) {
+        let lookahead_frames = if config.enabled {
+            ((config.lookahead_ms.max(0.0) / 1000.0 * sample_rate).round() as usize).max(1)
+        } else {
+            1
+        };
+        let resize = lookahead_frames != self.lookahead_frames;
+        let clear = resize || sample_rate != self.sample_rate;
+        self.config = config;
+        self.sample_rate = sample_rate;
+        self.ceiling = 10f32.powf(config.ceiling_db.min(0.0) / 20.0);
+        self.dc_coefficient = 1.0 - std::f32::consts::TAU * DC_BLOCK_HZ / sample_rate;
+        self.release_coefficient = 1.0 - (-1.0 / (config.release_ms.max(1.0) / 1000.0 * sample_rate)).exp();
+        self.lookahead_frames = lookahead_frames;
+        if resize {
+            self.resize(self.num_outputs);
+        } else if clear {
+            self.reset();
+        }
+    }
+
+    /// Sizes the buffers for the output stream's channel count. Not for the audio thread.
+    pub fn set_num_outputs(&mut self, num_outputs: usize) {
+        if num_outputs != self.num_outputs {
+            self.resize(num_outputs);
+        }
+    }
+
+    /// Seconds the stage delays the output by.
+    pub fn latency_seconds(&self) -> f32 {
+        if self.config.enabled { (self.lookahead_frames - 1) as f32 / self.sample_rate } else { 0.0 }
+    }
+
+    /// Allocates every buffer at its working size, with room for the frame each one takes
+    /// in before giving one back, so processing never grows them.
+    fn resize(&mut self, num_outputs: usize) {
+        self.num_outputs = num_outputs;
+        self.dc_state = vec![(0.0, 0.0); num_outputs];
+        let delay_len = (self.lookahead_frames - 1) * num_outputs;
+        self.delay = VecDeque::with_capacity(delay_len + num_outputs);
+        self.delay.resize(delay_len, 0.0);
+        self.held = VecDeque::with_capacity(self.lookahead_frames + 1);
+        self.smoothing = VecDeque::with_capacity(self.lookahead_frames + 1);
+        self.smoothing.resize(self.lookahead_frames, 1.0);
+        self.peaks = vec![0.0; num_outputs];
+        self.reset();
+    }
+
+    /// Clears the state in place, without allocating.
+    fn reset(&mut self) {
+        self.dc_state.fill((0.0, 0.0));
+        self.delay.iter_mut().for_each(|sample| *sample = 0.0);
+        self.held.clear();
+        self.released_gain = 1.0;
+        self.smoothing.iter_mut().for_each(|gain| *gain = 1.0);
+        self.smoothing_sum = self.lookahead_frames as f64;
+    }
+
+    /// Processes a buffer of interleaved frames in place and updates the meters.
+    pub fn process(&mut self, buffer: &mut [f32], num_outputs: usize) {
+        if num_outputs != self.num_outputs {
+            // Sized for another stream; hold the ceiling until set_num_outputs catches up
+            for sample in buffer.iter_mut() {
+                *sample = sample.clamp(-self.ceiling, self.ceiling);
+            }
+            return;
+        }
+        self.peaks.fill(0.0);
+        let mut lowest_gain = 1.0f32;
+
+        for frame in buffer.chunks_exact_mut(num_outputs) {
+            if self.config.dc_block {
+                for (sample, (last_in, last_out)) in frame.iter_mut().zip(self.dc_state.iter_mut()) {
+                    let out = *sample - *last_in + self.dc_coefficient * *last_out;
+                    *last_in = *sample;
+                    *last_out = out;
+                    *sample = out;
+                }
+            }
+
+            if self.config.enabled {
+                let gain = self.next_gain(frame);
+                lowest_gain = lowest_gain.min(gain);
+                self.delay.extend(frame.iter().copied());
+                for sample in frame.iter_mut() {
+                    *sample = self.delay.pop_front().unwrap_or(0.0) * gain;
+                }
+            }
+
+            for (sample, peak) in frame.iter_mut().zip(self.peaks.iter_mut()) {
+                if self.config.soft_clip {
+                    *sample = self.ceiling * (*sample / self.ceiling).tanh();
+                }
+                if self.config.enabled {
+                    // Rounding in the smoothing sum can leave a hair over the ceiling
+                    *sample = sample.clamp(-self.ceiling, self.ceiling);
+                }
+                *peak = peak.max(sample.abs());
+            }
+        }
+
+        // The GUI can wait for the next buffer; the audio callback must not
+        if let Ok(mut meters) = self.meters.try_lock() {
+            meters.peak_db.clear();
+            meters.peak_db.extend(self.peaks.iter().map(|&peak| 20.0 * peak.max(1e-6).log10()));
+            meters.gain_reduction_db = 20.0 * lowest_gain.max(1e-6).log10();
+        }
+    }
+
+    /// Gain for the frame leaving the delay line, given the frame entering it.
+    fn next_gain(&mut self, frame: &[f32]) -> f32 {
+        let peak = frame.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        let needed = if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+
+        // Lowest needed gain over the look-ahead window
+        let index = self.frame_index;
+        self.frame_index = self.frame_index.wrapping_add(1);
+        while self.held.back().is_some_and(|&(_, gain)| gain >= needed) {
+            self.held.pop_back();
+        }
+        self.held.push_back((index, needed));
+        while self.held.front().is_some_and(|&(start, _)| index.wrapping_sub(start) >= self.lookahead_frames) {
+            self.held.pop_front();
+        }
+        let held = self.held.front().map_or(1.0, |&(_, gain)| gain);
+
+        // Drop straight to the held gain, recover towards 1 at the release rate
+        self.released_gain = held.min(self.released_gain + (1.0 - self.released_gain) * self.release_coefficient);
+
+        self.smoothing.push_back(self.released_gain);
+        self.smoothing_sum += self.released_gain as f64;
+        if let Some(oldest) = self.smoothing.pop_front() {
+            self.smoothing_sum -= oldest as f64;
+        }
+        (self.smoothing_sum / self.lookahead_frames as f64) as f32
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// One partial of the latest frame, as the live bank should play it.
+#[derive(Debug, Clone, Copy)]
+pub struct LiveTarget {
+    /// Identifies the partial across frames so its oscillator keeps its phase
+    pub key: u64,
+    pub output: usize,
+    pub freq: f32,
+    pub amp: f32,
+}
+
+/// A phasor oscillator like the freeze bank's, whose frequency and amplitude follow
+/// the live partials.
+struct LiveOscillator {
+    output: usize,
+    amp: f32,
+    amp_step: f32,
+    target_amp: f32,
+    re: f32,
+    im: f32,
+    step_re: f32,
+    step_im: f32,
+}
+
+/// Oscillators driven straight from each analysis frame for low-latency resynthesis.
+/// New targets are picked up at the next audio block and amplitudes ramp across that
+/// block, instead of waiting for a pre-rendered segment.
+///
+/// `begin_block` runs in the audio callback, so it neither allocates nor frees: room for
+/// new oscillators is reserved when targets are queued, and the targets' buffer goes back
+/// to be refilled by the next `set_targets`.
+#[derive(Default)]
+pub struct LiveBank {
+    oscillators: HashMap<u64, LiveOscillator>,
+    pending: Option<(Vec<LiveTarget>, Instant)>,
+    /// Adopted targets' buffer, kept for reuse
+    spare: Option<Vec<LiveTarget>>,
+    /// Seconds from a frame's audio arriving to its targets being adopted by the audio
+    /// callback, for the latest frame adopted
+    adopted_delay: Option<f32>,
+}
+
+impl LiveBank {
+    /// Queues a frame's partials for the next audio block, replacing any not yet adopted.
+    /// `captured_at` is when the newest audio in the frame was captured.
+    pub fn set_targets(&mut self, targets: &[LiveTarget], captured_at: Instant) {
+        let mut buffer = self.pending.take()
+            .map(|(buffer, _)| buffer)
+            .or_else(|| self.spare.take())
+            .unwrap_or_default();
+        buffer.clear();
+        buffer.extend_from_slice(targets);
+        // Enough for every target to be new while the current oscillators fade out
+        self.oscillators.reserve(targets.len());
+        self.pending = Some((buffer, captured_at));
+    }
+
+    /// Delay from capture to adoption of the latest frame, taken so each is reported once.
+    pub fn take_adopted_delay(&mut self) -> Option<f32> {
+        self.adopted_delay.take()
+    }
+
+    /// Adopts pending targets at the start of a block of `frames` frames: matching
+    /// oscillators retune and ramp to their new amplitude, new ones ramp in from
+    /// silence and ones without a target ramp out.
+    pub fn begin_block(&mut self, frames: usize, sample_rate: f32) {
+        let Some((mut targets, captured_at)) = self.pending.take() else {
+            return;
+        };
+        self.adopted_delay = Some(captured_at.elapsed().as_secs_f32());
+        let ramp = 1.0 / frames.max(1) as f32;
+        let nyquist = sample_rate / 2.0;
+
+        for osc in self.oscillators.values_mut() {
+            osc.target_amp = 0.0;
+        }
+        for target in targets.iter().filter(|t| t.freq > 0.0 && t.freq < nyquist && t.amp > 0.0) {
+            let step = std::f32::consts::TAU * target.freq / sample_rate;
+            let osc = self.oscillators.entry(target.key).or_insert(LiveOscillator {
+                output: target.output,
+                amp: 0.0,
+                amp_step: 0.0,
+                target_amp: 0.0,
+                re: 1.0,
+                im: 0.0,
+                step_re: 1.0,
+                step_im: 0.0,
+            });
+            osc.output = target.output;
+            osc.target_amp = target.amp;
+            osc.step_re = step.cos();
+            osc.step_im = step.sin();
+        }
+        for osc in self.oscillators.values_mut() {
+            osc.amp_step = (osc.target_amp - osc.amp) * ramp;
+        }
+        targets.clear();
+        self.spare = Some(targets);
+    }
+
+    /// Adds one frame of every oscillator into an interleaved output frame.
+    pub fn add_frame(&mut self, frame: &mut [f32]) {
+        for osc in self.oscillators.values_mut() {
+            osc.amp += osc.amp_step;
+            if let Some(sample) = frame.get_mut(osc.output) {
+                *sample += osc.amp * osc.im;
+            }
+            let re = osc.re * osc.step_re - osc.im * osc.step_im;
+            osc.im = osc.re * osc.step_im + osc.im * osc.step_re;
+            osc.re = re;
+        }
+    }
+
+    /// Settles each ramp on its target, drops oscillators that have faded out and
+    /// renormalises the phasors. Called once per buffer.
+    pub fn end_block(&mut self) {
+        self.oscillators.retain(|_, osc| osc.target_amp > 0.0);
+        for osc in self.oscillators.values_mut() {
+            osc.amp = osc.target_amp;
+            osc.amp_step = 0.0;
+            let magnitude = (osc.re * osc.re + osc.im * osc.im).sqrt();
+            if magnitude > 0.0 {
+                osc.re /= magnitude;
+                osc.im /= magnitude;
+            }
+        }
+    }
+}
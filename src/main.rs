@@ -5,6 +5,25 @@ mod display;
 mod resynth;
 mod get_results;
 mod presets;
+mod crosstalk_calibration;
+mod pitch;
+mod harmonics;
+mod tuning;
+mod descriptors;
+mod onset;
+mod partial_tracker;
+mod osc;
+mod averaging;
+mod waterfall;
+mod reference;
+mod routing;
+mod residual;
+mod transforms;
+mod freeze;
+mod live_bank;
+mod limiter;
+mod midi;
+mod midi_file;
 
 use clap::Parser;
 use std::sync::LazyLock;
@@ -45,6 +64,10 @@ struct Args {
     #[arg(short = 'p', long)]
     num_partials: Option<usize>,
     
+    /// Send detected onsets as OSC messages to this UDP address, e.g. "127.0.0.1:9000"
+    #[arg(long = "osc-target")]
+    osc_target: Option<String>,
+
     /// Enable info logging
     #[arg(long)]
     info: bool,
@@ -98,8 +121,12 @@ use eframe::NativeOptions;
 use log::{info, error, warn, debug, LevelFilter};
 use fern::Dispatch;
 use env_logger;
-use fft_analysis::{FFTConfig, MAX_SPECTROGRAPH_HISTORY, start_fft_processing};
+use fft_analysis::{FFTConfig, start_fft_processing, AnalysisData, ChannelAnalysis};
 use crate::resynth::{ResynthConfig, start_resynth_thread};
+use crate::routing::OutputRouting;
+use crate::limiter::{LimiterConfig, OutputMeters};
+use crate::midi::MidiConfig;
+use crate::midi_file::SessionRecording;
 use std::thread;
 use std::time::{Duration, Instant};
 use std::collections::VecDeque;
@@ -189,6 +216,55 @@ async fn shared_memory_updater_loop(
     info!(target: "shared_memory", "Shared memory update loop shutting down.");
 }
 
+/// Writes the per-channel analysis to its own shared memory file as fixed-size
+/// records of `ChannelAnalysis::shared_memory_record_len` f32 values per channel.
+async fn analysis_memory_updater_loop(
+    mut analysis_rx: broadcast::Receiver<AnalysisData>,
+    shared_memory_path: String,
+    num_partials: usize,
+    shutdown_flag: Arc<AtomicBool>,
+) {
+    debug!(target: "shared_memory", "Starting analysis shared memory update loop for path: {}", shared_memory_path);
+
+    while !shutdown_flag.load(Ordering::Relaxed) {
+        match analysis_rx.recv().await {
+            Ok(analysis) => {
+                let mut bytes_to_write = Vec::<u8>::new();
+                for channel in &analysis {
+                    for value in channel.shared_memory_record(num_partials) {
+                        bytes_to_write.extend_from_slice(&value.to_ne_bytes());
+                    }
+                }
+
+                match OpenOptions::new().read(true).write(true).open(&shared_memory_path) {
+                    Ok(file) => {
+                        match unsafe { MmapMut::map_mut(&file) } {
+                            Ok(mut mmap) => {
+                                let len = bytes_to_write.len().min(mmap.len());
+                                mmap[..len].copy_from_slice(&bytes_to_write[..len]);
+                            }
+                            Err(e) => {
+                                error!(target: "shared_memory", "Failed to memory map file {}: {}", shared_memory_path, e);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error!(target: "shared_memory", "Failed to open shared memory file {}: {}", shared_memory_path, e);
+                    }
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!(target: "shared_memory", "Shared memory analysis receiver lagged by {} messages.", n);
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                info!(target: "shared_memory", "Analysis broadcast channel closed for shared memory.");
+                break;
+            }
+        }
+    }
+    info!(target: "shared_memory", "Analysis shared memory update loop shutting down.");
+}
+
 fn calculate_max_freq(sample_rate: f64) -> f64 {
     sample_rate / 2.0
 }
@@ -779,6 +855,9 @@ fn run(args: &Args) -> Result<()> {
         selected_channels.len()
     )));
     let spectrum_app = Arc::new(Mutex::new(plot::SpectrumApp::new(selected_channels.len())));
+    let crosstalk_calibration = Arc::new(Mutex::new(
+        crosstalk_calibration::CrosstalkCalibration::new(selected_channels.len(), selected_input_sample_rate as f32)
+    ));
     
     let mut config = FFTConfig::default();
     // Override only what needs to be different from defaults
@@ -822,6 +901,17 @@ fn run(args: &Args) -> Result<()> {
         gain: 0.5,
         freq_scale: 1.0,
         update_rate: 1.0,
+        onset_boundaries: false,
+        routing: OutputRouting::default(),
+        noise_level: 0.0,
+        transpose_semitones: 0.0,
+        transpose_cents: 0.0,
+        preserve_formants: false,
+        transforms: Vec::new(),
+        low_latency: false,
+        latency: Arc::new(Mutex::new(None)),
+        limiter: LimiterConfig::default(),
+        meters: Arc::new(Mutex::new(OutputMeters::default())),
         needs_restart: Arc::new(AtomicBool::new(false)),
         needs_stop: Arc::new(AtomicBool::new(false)),
         output_sample_rate: Arc::new(Mutex::new(selected_output_sample_rate)),
@@ -840,7 +930,8 @@ fn run(args: &Args) -> Result<()> {
     // Write control file for external processes
     let control_path = format!("{}/audio_control", shm_dir);
     let mut control_file = std::fs::File::create(&control_path)?;
-    writeln!(control_file, "{}\n{}\n{}", std::process::id(), selected_channels.len(), num_partials)?;
+    writeln!(control_file, "{}\n{}\n{}\n{}", std::process::id(), selected_channels.len(), num_partials,
+             ChannelAnalysis::shared_memory_record_len(num_partials))?;
 
     // Shared state for shutdown and timers
     let shutdown_flag = Arc::new(AtomicBool::new(false));
@@ -849,8 +940,8 @@ fn run(args: &Args) -> Result<()> {
     let running = Arc::new(AtomicBool::new(true));
     let start_time = Arc::new(Instant::now());
 
-    // Before starting the FFT thread, initialize spectrograph history with fixed capacity
-    let spectrograph_history = Arc::new(Mutex::new(VecDeque::<SpectrographSlice>::with_capacity(MAX_SPECTROGRAPH_HISTORY)));
+    // Before starting the FFT thread, initialize spectrograph history; the FFT thread trims it by age
+    let spectrograph_history = Arc::new(Mutex::new(VecDeque::<SpectrographSlice>::new()));
     
     // Initialize shared memory without mutex BEFORE threads start
     let shared_partials = {
@@ -863,6 +954,10 @@ fn run(args: &Args) -> Result<()> {
             path: shared_memory_path,
         })
     };
+    let shared_analysis_path = format!("{}/audio_analysis", shm_dir);
+    // Sized for the layout announced in the control file: one record per channel
+    let analysis_memory_len = ChannelAnalysis::shared_memory_record_len(num_partials) * selected_channels.len() * std::mem::size_of::<f32>();
+    std::fs::File::create(&shared_analysis_path)?.set_len(analysis_memory_len as u64)?;
     
     // --- Thread variable setup ---
     let shutdown_flag_audio = Arc::clone(&shutdown_flag);
@@ -882,6 +977,10 @@ fn run(args: &Args) -> Result<()> {
     let resynth_config_resynth = Arc::clone(&resynth_config);
     let resynth_config_gui = Arc::clone(&resynth_config);
 
+    let midi_config = Arc::new(Mutex::new(MidiConfig::default()));
+    let midi_status = Arc::new(Mutex::new(String::from("Closed")));
+    let session_recording = Arc::new(Mutex::new(SessionRecording::default()));
+
     let stream_ready_audio = Arc::clone(&stream_ready);
     let stream_ready_fft = Arc::clone(&stream_ready);
     
@@ -895,6 +994,9 @@ fn run(args: &Args) -> Result<()> {
     let partials_rx_resynth = partials_tx.subscribe();
     let partials_rx_gui = partials_tx.subscribe();
 
+    let (analysis_tx, _) = broadcast::channel::<AnalysisData>(16);
+    let analysis_tx_fft = analysis_tx.clone();
+
     let (gui_param_tx_gui, gui_param_rx_resynth) = mpsc::channel::<GuiParameter>();
     let (gain_update_tx_gui, gain_update_rx_resynth) = mpsc::channel::<f32>();
     
@@ -939,6 +1041,8 @@ fn run(args: &Args) -> Result<()> {
         partials_tx_fft,
         Some(Arc::clone(&spectrograph_history)),
         Some(Arc::clone(&start_time)),
+        Arc::clone(&crosstalk_calibration),
+        analysis_tx_fft,
     );
     let _fft_thread = thread::spawn(move || {
         start_fft_processing(
@@ -951,6 +1055,8 @@ fn run(args: &Args) -> Result<()> {
             fft_thread_args.6,
             fft_thread_args.7,
             fft_thread_args.8,
+            fft_thread_args.9,
+            fft_thread_args.10,
         );
     });
 
@@ -965,6 +1071,7 @@ fn run(args: &Args) -> Result<()> {
         num_partials_resynth,
         gui_param_rx_resynth,
         gain_update_rx_resynth,
+        analysis_tx.subscribe(),
     );
     let _resynth_thread = std::thread::spawn({
         move || {
@@ -978,6 +1085,7 @@ fn run(args: &Args) -> Result<()> {
                 resynth_thread_args.6,
                 resynth_thread_args.7,
                 resynth_thread_args.8,
+                resynth_thread_args.9,
             );
         }
     });
@@ -1002,6 +1110,10 @@ fn run(args: &Args) -> Result<()> {
         partials_rx_gui,
         gui_param_tx_gui,
         gain_update_tx_gui,
+        crosstalk_calibration,
+        Arc::clone(&midi_config),
+        Arc::clone(&midi_status),
+        Arc::clone(&session_recording),
     );
     
     // Spawn SharedMemory update thread
@@ -1016,6 +1128,48 @@ fn run(args: &Args) -> Result<()> {
     } else {
         warn!("SharedMemory struct not initialized, skipping shared memory update thread.");
     }
+
+    // Spawn the analysis shared memory update thread
+    {
+        let analysis_rx = analysis_tx.subscribe();
+        let sm_shutdown_flag = Arc::clone(&shutdown_flag);
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(analysis_memory_updater_loop(analysis_rx, shared_analysis_path, num_partials, sm_shutdown_flag));
+        });
+    }
+
+    // Spawn the MIDI sender; it stays idle until MIDI output is enabled in the GUI
+    {
+        let partials_rx = partials_tx.subscribe();
+        let analysis_rx = analysis_tx.subscribe();
+        let midi_shutdown_flag = Arc::clone(&shutdown_flag);
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(midi::midi_sender_loop(partials_rx, analysis_rx, midi_config, midi_status, midi_shutdown_flag));
+        });
+    }
+
+    // Spawn the session recorder; it only keeps frames while recording from the GUI
+    {
+        let partials_rx = partials_tx.subscribe();
+        let analysis_rx = analysis_tx.subscribe();
+        let recorder_shutdown_flag = Arc::clone(&shutdown_flag);
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(midi_file::recorder_loop(partials_rx, analysis_rx, session_recording, recorder_shutdown_flag));
+        });
+    }
+
+    // Spawn the OSC onset sender if a target was given
+    if let Some(osc_target) = args.osc_target.clone() {
+        let analysis_rx = analysis_tx.subscribe();
+        let osc_shutdown_flag = Arc::clone(&shutdown_flag);
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(osc::onset_sender_loop(analysis_rx, osc_target, osc_shutdown_flag));
+        });
+    }
     
     let native_options = NativeOptions {
         viewport: ViewportBuilder::default()
@@ -0,0 +1,450 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use log::{debug, info, warn};
+use tokio::sync::broadcast;
+use crate::fft_analysis::AnalysisData;
+
+type PartialsData = Vec<Vec<(f32, f32)>>;
+
+// Semitones a note may drift from its key before it is retriggered on the new key: just
+// past half a semitone, so a pitch wavering at the boundary doesn't chatter
+pub const RETRIGGER_SEMITONES: f32 = 0.6;
+// Pitch bend changes smaller than this (out of ±8192) are not sent
+pub const BEND_DEADBAND: i32 = 16;
+// Wait between attempts to open the port after a failure
+const REOPEN_INTERVAL: Duration = Duration::from_secs(2);
+// MIDI member channels of an MPE lower zone (0-based): channel 1 is the manager
+pub const MPE_MEMBER_CHANNELS: std::ops::RangeInclusive<u8> = 1..=15;
+
+/// What the MIDI output plays.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum MidiSource {
+    /// One note per channel at the estimated fundamental
+    #[default]
+    Pitch,
+    /// The loudest tracked partials of each channel
+    Partials,
+}
+
+impl MidiSource {
+    pub const ALL: [MidiSource; 2] = [MidiSource::Pitch, MidiSource::Partials];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MidiSource::Pitch => "Pitch",
+            MidiSource::Partials => "Partials",
+        }
+    }
+}
+
+/// Settings for turning the analysis into MIDI notes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiConfig {
+    pub enabled: bool,
+    pub port_name: String,       // Virtual port to create, or on Windows the output port to connect to (by substring)
+    pub source: MidiSource,
+    pub top_partials: usize,     // Partials per channel played in Partials mode
+    pub mpe: bool,               // Every note on its own member channel of an MPE lower zone
+    pub base_channel: u8,        // 1-based MIDI channel of the first input channel without MPE
+    pub bend_range: f32,         // Semitones at full pitch bend, sent to the receiver as RPN 0
+    pub min_confidence: f32,     // Pitch mode: less periodic frames send no note
+    pub velocity_floor_db: f32,  // dBFS at velocity 1; quieter partials send no note
+    pub velocity_ceiling_db: f32, // dBFS at velocity 127
+}
+
+impl Default for MidiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port_name: "audio_streaming".to_string(),
+            source: MidiSource::Pitch,
+            top_partials: 4,
+            mpe: false,
+            base_channel: 1,
+            bend_range: 2.0,
+            min_confidence: 0.8,
+            velocity_floor_db: -60.0,
+            velocity_ceiling_db: -6.0,
+        }
+    }
+}
+
+impl MidiConfig {
+    /// Whether a change from `other` alters how notes map to channels and bends, so
+    /// sounding notes must end and the receiver be set up again.
+    fn layout_differs(&self, other: &MidiConfig) -> bool {
+        self.source != other.source
+            || self.mpe != other.mpe
+            || self.base_channel != other.base_channel
+            || self.bend_range != other.bend_range
+    }
+
+    /// Whether each note gets a channel to itself, so its pitch bend can follow it.
+    pub fn bends_notes(&self) -> bool {
+        self.mpe || self.source == MidiSource::Pitch
+    }
+
+    /// MIDI channel (0-based) of an input channel outside MPE, or `None` for inputs that
+    /// would run past channel 16 rather than share a channel with an earlier input.
+    pub fn input_channel(&self, input: usize) -> Option<u8> {
+        let channel = self.base_channel.clamp(1, 16) as usize - 1 + input;
+        (channel < 16).then_some(channel as u8)
+    }
+}
+
+/// Fractional MIDI note number of `freq`, with A4 at 440 Hz as receivers expect.
+pub fn midi_pitch(freq: f32) -> f32 {
+    69.0 + 12.0 * (freq / 440.0).log2()
+}
+
+/// Level in dBFS of a channel's fundamental: its strongest partial within a semitone
+/// of `f0`, else its loudest partial. Negative infinity with no partials.
+pub fn fundamental_level(channel_partials: &[(f32, f32)], f0: f32) -> f32 {
+    let sounding = channel_partials.iter().filter(|(freq, _)| *freq > 0.0);
+    let near_f0 = sounding.clone()
+        .filter(|&&(freq, _)| (midi_pitch(freq) - midi_pitch(f0)).abs() < 1.0)
+        .fold(f32::NEG_INFINITY, |loudest, &(_, db)| loudest.max(db));
+    if near_f0.is_finite() {
+        near_f0
+    } else {
+        sounding.fold(f32::NEG_INFINITY, |loudest, &(_, db)| loudest.max(db))
+    }
+}
+
+/// 14-bit pitch bend value, 8192 at centre, for `semitones` off the note.
+pub fn bend_value(semitones: f32, bend_range: f32) -> i32 {
+    (8192.0 + semitones / bend_range.max(0.01) * 8192.0).round().clamp(0.0, 16383.0) as i32
+}
+
+/// Velocity 1 to 127 for a level in dBFS between the floor and ceiling.
+pub fn velocity(db: f32, floor_db: f32, ceiling_db: f32) -> u8 {
+    let span = (ceiling_db - floor_db).max(1.0);
+    (1.0 + 126.0 * ((db - floor_db) / span).clamp(0.0, 1.0)).round() as u8
+}
+
+fn note_on(channel: u8, note: u8, velocity: u8) -> [u8; 3] {
+    [0x90 | channel, note, velocity.clamp(1, 127)]
+}
+
+fn note_off(channel: u8, note: u8) -> [u8; 3] {
+    [0x80 | channel, note, 0]
+}
+
+fn pitch_bend(channel: u8, value: i32) -> [u8; 3] {
+    [0xE0 | channel, (value & 0x7F) as u8, ((value >> 7) & 0x7F) as u8]
+}
+
+fn control_change(channel: u8, controller: u8, value: u8) -> [u8; 3] {
+    [0xB0 | channel, controller, value & 0x7F]
+}
+
+/// Sets a registered parameter (RPN) on a channel, then deselects it.
+fn push_rpn(messages: &mut Vec<[u8; 3]>, channel: u8, parameter: u8, msb: u8, lsb: u8) {
+    messages.push(control_change(channel, 101, 0));
+    messages.push(control_change(channel, 100, parameter));
+    messages.push(control_change(channel, 6, msb));
+    messages.push(control_change(channel, 38, lsb));
+    messages.push(control_change(channel, 101, 127));
+    messages.push(control_change(channel, 100, 127));
+}
+
+/// Messages that prepare a receiver: with MPE, a lower zone over all 15 member channels
+/// (RPN 6 on the manager channel); then the pitch bend range (RPN 0) on every channel.
+pub fn setup_messages(config: &MidiConfig) -> Vec<[u8; 3]> {
+    let mut messages = Vec::new();
+    if config.mpe {
+        push_rpn(&mut messages, 0, 6, *MPE_MEMBER_CHANNELS.end(), 0);
+    }
+    let semitones = config.bend_range.clamp(0.0, 127.0).floor();
+    let cents = ((config.bend_range.clamp(0.0, 127.0) - semitones) * 100.0).round().min(99.0);
+    for channel in 0..16 {
+        push_rpn(&mut messages, channel, 0, semitones as u8, cents as u8);
+    }
+    messages
+}
+
+/// A sounding note and what it follows.
+struct Voice {
+    channel: u8,
+    note: u8,
+    bend: i32,
+    started: u64, // Order of note-on, for stealing the oldest MPE channel
+}
+
+/// Follows the analysis frame by frame and works out the note-on, note-off and pitch
+/// bend messages that keep a receiver's notes in line with it.
+#[derive(Default)]
+pub struct MidiMapper {
+    /// Keyed by input channel and track ID (0 for the fundamental in Pitch mode)
+    voices: HashMap<(usize, u32), Voice>,
+    notes_started: u64,
+    warned_unmapped_inputs: bool,
+}
+
+impl MidiMapper {
+    /// Note-offs for every sounding note.
+    pub fn all_notes_off(&mut self) -> Vec<[u8; 3]> {
+        self.voices.drain().map(|(_, voice)| note_off(voice.channel, voice.note)).collect()
+    }
+
+    /// Messages for one analysis frame.
+    pub fn frame(&mut self, config: &MidiConfig, partials: &PartialsData, analysis: &AnalysisData) -> Vec<[u8; 3]> {
+        // What should sound: key, Hz and dBFS
+        let mut wanted: Vec<((usize, u32), f32, f32)> = Vec::new();
+        for (input, channel_partials) in partials.iter().enumerate() {
+            let channel_analysis = analysis.get(input);
+            match config.source {
+                MidiSource::Pitch => {
+                    let pitch = channel_analysis.and_then(|a| a.pitch).filter(|p| p.confidence >= config.min_confidence && p.f0 > 0.0);
+                    let Some(pitch) = pitch else { continue };
+                    wanted.push(((input, 0), pitch.f0, fundamental_level(channel_partials, pitch.f0)));
+                }
+                MidiSource::Partials => {
+                    let tracks = channel_analysis.map_or(&[][..], |a| a.tracks.as_slice());
+                    let mut candidates: Vec<((usize, u32), f32, f32)> = channel_partials.iter()
+                        .zip(tracks)
+                        .filter(|(&(freq, _), _)| freq > 0.0)
+                        .filter_map(|(&(freq, db), track)| track.map(|track| ((input, track), freq, db)))
+                        .collect();
+                    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+                    candidates.truncate(config.top_partials);
+                    wanted.extend(candidates);
+                }
+            }
+        }
+        wanted.retain(|&(_, freq, db)| db >= config.velocity_floor_db && (0.0..=127.0).contains(&midi_pitch(freq).round()));
+
+        // End notes that are no longer wanted, have moved to another key, or restart at an onset
+        let onsets: Vec<bool> = analysis.iter().map(|channel| channel.onset.is_some()).collect();
+        let mut messages = Vec::new();
+        self.voices.retain(|&key, voice| {
+            let keep = wanted.iter()
+                .find(|(wanted_key, _, _)| *wanted_key == key)
+                .is_some_and(|&(_, freq, _)| (midi_pitch(freq) - voice.note as f32).abs() <= RETRIGGER_SEMITONES)
+                && !onsets.get(key.0).copied().unwrap_or(false);
+            if !keep {
+                messages.push(note_off(voice.channel, voice.note));
+            }
+            keep
+        });
+
+        for (key, freq, db) in wanted {
+            let pitch = midi_pitch(freq);
+            if let Some(voice) = self.voices.get_mut(&key) {
+                if config.bends_notes() {
+                    let bend = bend_value(pitch - voice.note as f32, config.bend_range);
+                    if (bend - voice.bend).abs() >= BEND_DEADBAND {
+                        messages.push(pitch_bend(voice.channel, bend));
+                        voice.bend = bend;
+                    }
+                }
+                continue;
+            }
+
+            let note = pitch.round() as u8;
+            let channel = if config.mpe {
+                self.free_mpe_channel(&mut messages)
+            } else {
+                let Some(channel) = config.input_channel(key.0) else {
+                    if !self.warned_unmapped_inputs {
+                        warn!(target: "midi", "Input channels past MIDI channel 16 send no notes; lower the first channel or use MPE");
+                        self.warned_unmapped_inputs = true;
+                    }
+                    continue;
+                };
+                // Partials sharing a channel can't both hold a key: the louder one keeps it
+                if self.voices.values().any(|voice| voice.channel == channel && voice.note == note) {
+                    continue;
+                }
+                channel
+            };
+            let bend = if config.bends_notes() { bend_value(pitch - note as f32, config.bend_range) } else { 8192 };
+            if config.bends_notes() {
+                messages.push(pitch_bend(channel, bend));
+            }
+            messages.push(note_on(channel, note, velocity(db, config.velocity_floor_db, config.velocity_ceiling_db)));
+            self.notes_started += 1;
+            self.voices.insert(key, Voice { channel, note, bend, started: self.notes_started });
+        }
+        messages
+    }
+
+    /// An MPE member channel with no note on it, ending the oldest note if all are taken.
+    fn free_mpe_channel(&mut self, messages: &mut Vec<[u8; 3]>) -> u8 {
+        if let Some(channel) = MPE_MEMBER_CHANNELS.clone().find(|&channel| self.voices.values().all(|voice| voice.channel != channel)) {
+            return channel;
+        }
+        let oldest = self.voices.iter().min_by_key(|(_, voice)| voice.started).map(|(&key, _)| key);
+        let voice = oldest.and_then(|key| self.voices.remove(&key));
+        match voice {
+            Some(voice) => {
+                messages.push(note_off(voice.channel, voice.note));
+                voice.channel
+            }
+            None => *MPE_MEMBER_CHANNELS.start(),
+        }
+    }
+}
+
+#[cfg(feature = "midi")]
+mod port {
+    use midir::{MidiOutput, MidiOutputConnection};
+
+    /// An open MIDI output.
+    pub struct MidiPort(MidiOutputConnection);
+
+    impl MidiPort {
+        /// Creates a virtual output port other applications can connect to.
+        #[cfg(unix)]
+        pub fn open(name: &str) -> Result<Self, anyhow::Error> {
+            use midir::os::unix::VirtualOutput;
+            let output = MidiOutput::new("audio_streaming").map_err(|e| anyhow::anyhow!("{}", e))?;
+            let connection = output.create_virtual(name).map_err(|e| anyhow::anyhow!("{}", e))?;
+            Ok(Self(connection))
+        }
+
+        /// Connects to the first output port whose name contains `name`; Windows has no
+        /// virtual ports.
+        #[cfg(not(unix))]
+        pub fn open(name: &str) -> Result<Self, anyhow::Error> {
+            let output = MidiOutput::new("audio_streaming").map_err(|e| anyhow::anyhow!("{}", e))?;
+            let port = output.ports().into_iter()
+                .find(|port| output.port_name(port).is_ok_and(|port_name| port_name.contains(name)))
+                .ok_or_else(|| anyhow::anyhow!("No MIDI output port matching \"{}\"", name))?;
+            let connection = output.connect(&port, "audio_streaming").map_err(|e| anyhow::anyhow!("{}", e))?;
+            Ok(Self(connection))
+        }
+
+        pub fn send(&mut self, message: &[u8]) -> Result<(), anyhow::Error> {
+            self.0.send(message).map_err(|e| anyhow::anyhow!("{}", e))
+        }
+    }
+}
+
+#[cfg(not(feature = "midi"))]
+mod port {
+    /// Stands in for the MIDI output when built without the `midi` feature.
+    pub struct MidiPort;
+
+    impl MidiPort {
+        pub fn open(_name: &str) -> Result<Self, anyhow::Error> {
+            Err(anyhow::anyhow!("Built without MIDI support; rebuild with --features midi"))
+        }
+
+        pub fn send(&mut self, _message: &[u8]) -> Result<(), anyhow::Error> {
+            Ok(())
+        }
+    }
+}
+
+use port::MidiPort;
+
+fn send_all(port: &mut MidiPort, messages: &[[u8; 3]]) {
+    for message in messages {
+        if let Err(e) = port.send(message) {
+            warn!(target: "midi", "Failed to send MIDI message {:02X?}: {}", message, e);
+        }
+    }
+}
+
+fn set_status(status: &Mutex<String>, text: String) {
+    if let Ok(mut status) = status.lock() {
+        *status = text;
+    }
+}
+
+/// Plays the analysis as MIDI notes on the configured port while enabled, opening the
+/// port on demand and ending every note when disabled or when the note layout changes.
+/// `status` describes the port's state for the GUI.
+pub async fn midi_sender_loop(
+    mut partials_rx: broadcast::Receiver<PartialsData>,
+    mut analysis_rx: broadcast::Receiver<AnalysisData>,
+    config: Arc<Mutex<MidiConfig>>,
+    status: Arc<Mutex<String>>,
+    shutdown_flag: Arc<AtomicBool>,
+) {
+    let mut port: Option<MidiPort> = None;
+    let mut port_config: Option<MidiConfig> = None; // Settings the open port was set up with
+    let mut last_attempt: Option<Instant> = None;
+    let mut mapper = MidiMapper::default();
+    let mut latest_analysis: AnalysisData = Vec::new();
+
+    while !shutdown_flag.load(Ordering::Relaxed) {
+        let partials = match partials_rx.recv().await {
+            Ok(partials) => partials,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!(target: "midi", "MIDI partials receiver lagged by {} messages.", n);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                info!(target: "midi", "Partials broadcast channel closed for MIDI output.");
+                break;
+            }
+        };
+        // A frame's analysis is published just before its partials
+        loop {
+            match analysis_rx.try_recv() {
+                Ok(analysis) => latest_analysis = analysis,
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+
+        let Ok(current) = config.lock().map(|config| config.clone()) else { continue };
+
+        // Close the port when disabled or renamed
+        let port_changed = port_config.as_ref().is_some_and(|open| open.port_name != current.port_name);
+        if !current.enabled || port_changed {
+            if let Some(mut open_port) = port.take() {
+                send_all(&mut open_port, &mapper.all_notes_off());
+                info!(target: "midi", "Closed MIDI output \"{}\"", port_config.as_ref().map_or("", |c| c.port_name.as_str()));
+                set_status(&status, "Closed".to_string());
+            }
+            port_config = None;
+            if !current.enabled {
+                continue;
+            }
+        }
+
+        if port.is_none() {
+            if last_attempt.is_some_and(|attempt| attempt.elapsed() < REOPEN_INTERVAL) {
+                continue;
+            }
+            last_attempt = Some(Instant::now());
+            match MidiPort::open(&current.port_name) {
+                Ok(mut opened) => {
+                    info!(target: "midi", "Opened MIDI output \"{}\"", current.port_name);
+                    send_all(&mut opened, &setup_messages(&current));
+                    set_status(&status, format!("Sending on \"{}\"", current.port_name));
+                    port = Some(opened);
+                    port_config = Some(current.clone());
+                }
+                Err(e) => {
+                    warn!(target: "midi", "Failed to open MIDI output \"{}\": {}", current.port_name, e);
+                    set_status(&status, format!("Error: {}", e));
+                    continue;
+                }
+            }
+        }
+        let Some(open_port) = port.as_mut() else { continue };
+
+        if port_config.as_ref().is_some_and(|open| open.layout_differs(&current)) {
+            debug!(target: "midi", "MIDI note layout changed; restarting notes");
+            send_all(open_port, &mapper.all_notes_off());
+            send_all(open_port, &setup_messages(&current));
+            port_config = Some(current.clone());
+        }
+
+        let messages = mapper.frame(&current, &partials, &latest_analysis);
+        send_all(open_port, &messages);
+    }
+
+    if let Some(mut open_port) = port.take() {
+        send_all(&mut open_port, &mapper.all_notes_off());
+    }
+    info!(target: "midi", "MIDI sender shutting down.");
+}
@@ -0,0 +1,589 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use anyhow::Result;
+use log::{info, warn};
+use tokio::sync::broadcast;
+use crate::fft_analysis::AnalysisData;
+use crate::midi::{
+    bend_value, fundamental_level, midi_pitch, setup_messages, velocity, MidiConfig, MidiSource,
+    BEND_DEADBAND, MPE_MEMBER_CHANNELS, RETRIGGER_SEMITONES,
+};
+
+type PartialsData = Vec<Vec<(f32, f32)>>;
+
+// Ticks per quarter note in exported files
+const TICKS_PER_QUARTER: u16 = 480;
+// Longest session kept, so a forgotten recording doesn't use up memory
+pub const MAX_RECORDING_SECONDS: f64 = 1800.0;
+
+/// One channel of a recorded analysis frame, levels in dBFS.
+struct RecordedChannel {
+    /// Estimated fundamental in Hz, its confidence and its level
+    pitch: Option<(f32, f32, f32)>,
+    onset: bool,
+    /// Track ID, Hz and level of each sounding partial
+    partials: Vec<(u32, f32, f32)>,
+}
+
+struct RecordedFrame {
+    time: f64,
+    channels: Vec<RecordedChannel>,
+}
+
+/// Partial tracks and pitch of every analysis frame while recording, for exporting as a
+/// Standard MIDI File.
+#[derive(Default)]
+pub struct SessionRecording {
+    frames: Vec<RecordedFrame>,
+    active: bool,
+    started: Option<Instant>,
+}
+
+impl SessionRecording {
+    /// Clears any previous take and records from the next frame.
+    pub fn start(&mut self) {
+        self.frames.clear();
+        self.started = None;
+        self.active = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Adds a frame whose newest audio was captured at `captured_at`. Recording stops by
+    /// itself once it reaches its maximum length.
+    pub fn push(&mut self, captured_at: Instant, partials: &PartialsData, analysis: &AnalysisData) {
+        if !self.active {
+            return;
+        }
+        let started = *self.started.get_or_insert(captured_at);
+        let time = captured_at.saturating_duration_since(started).as_secs_f64();
+        if time >= MAX_RECORDING_SECONDS {
+            warn!(target: "midi", "Recording reached {:.0} minutes and was stopped", MAX_RECORDING_SECONDS / 60.0);
+            self.active = false;
+            return;
+        }
+        let channels = partials.iter()
+            .enumerate()
+            .map(|(channel, channel_partials)| {
+                let channel_analysis = analysis.get(channel);
+                let tracks = channel_analysis.map_or(&[][..], |a| a.tracks.as_slice());
+                RecordedChannel {
+                    pitch: channel_analysis.and_then(|a| a.pitch).map(|pitch| {
+                        (pitch.f0, pitch.confidence, fundamental_level(channel_partials, pitch.f0))
+                    }),
+                    onset: channel_analysis.is_some_and(|a| a.onset.is_some()),
+                    partials: channel_partials.iter()
+                        .zip(tracks)
+                        .filter(|(&(freq, _), _)| freq > 0.0)
+                        .filter_map(|(&(freq, db), track)| track.map(|track| (track, freq, db)))
+                        .collect(),
+                }
+            })
+            .collect();
+        self.frames.push(RecordedFrame { time, channels });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn duration(&self) -> f64 {
+        match (self.frames.first(), self.frames.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    /// When each frame stops counting: the next frame's time, and for the last frame one
+    /// frame interval on.
+    fn frame_ends(&self) -> Vec<f64> {
+        let mut ends: Vec<f64> = self.frames.windows(2).map(|pair| pair[1].time).collect();
+        if let Some(last) = self.frames.last() {
+            let interval = match self.frames.len() {
+                0 | 1 => 0.01,
+                n => last.time - self.frames[n - 2].time,
+            };
+            ends.push(last.time + interval.max(0.001));
+        }
+        ends
+    }
+}
+
+/// How notes are placed in the exported file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportOptions {
+    pub tempo_bpm: f32,
+    pub quantize_division: u32, // Grid steps per quarter note that starts and ends snap to, 0 for none
+    pub min_duration_ms: f32,   // Shorter notes are left out, before quantising
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self { tempo_bpm: 120.0, quantize_division: 0, min_duration_ms: 50.0 }
+    }
+}
+
+/// A note taken from the recording, with its pitch through time.
+struct ExportNote {
+    input: usize,
+    start: f64,
+    end: f64,
+    peak_db: f32,
+    /// Frame times and fractional MIDI pitches
+    pitches: Vec<(f64, f32)>,
+}
+
+impl ExportNote {
+    fn new(input: usize, start: f64, end: f64, pitch: f32, db: f32) -> Self {
+        Self { input, start, end, peak_db: db, pitches: vec![(start, pitch)] }
+    }
+
+    fn extend(&mut self, time: f64, end: f64, pitch: f32, db: f32) {
+        self.end = end;
+        self.peak_db = self.peak_db.max(db);
+        self.pitches.push((time, pitch));
+    }
+
+    /// MIDI key: where the pitch mostly sits, so bends stay small.
+    fn key(&self) -> u8 {
+        let mean = self.pitches.iter().map(|&(_, pitch)| pitch).sum::<f32>() / self.pitches.len().max(1) as f32;
+        mean.round().clamp(0.0, 127.0) as u8
+    }
+}
+
+/// Carries on the note sounding under `key`, or starts a new one when the pitch has moved
+/// past `RETRIGGER_SEMITONES` from where it started or the input had an onset, as the live
+/// output retriggers. Returns the note that ended.
+fn continue_note(
+    sounding: &mut HashMap<(usize, u32), ExportNote>,
+    key: (usize, u32),
+    onset: bool,
+    time: f64,
+    end: f64,
+    pitch: f32,
+    db: f32,
+) -> Option<ExportNote> {
+    let continues = sounding.get(&key).is_some_and(|note| {
+        (pitch - note.pitches[0].1.round()).abs() <= RETRIGGER_SEMITONES && !onset
+    });
+    if continues {
+        if let Some(note) = sounding.get_mut(&key) {
+            note.extend(time, end, pitch, db);
+        }
+        None
+    } else {
+        sounding.insert(key, ExportNote::new(key.0, time, end, pitch, db))
+    }
+}
+
+/// Notes from the recording the way the live MIDI output would play them: a note per
+/// partial track, or per steady stretch of each channel's pitch.
+fn collect_notes(recording: &SessionRecording, config: &MidiConfig) -> Vec<ExportNote> {
+    let ends = recording.frame_ends();
+    let mut notes = Vec::new();
+    let mut sounding: HashMap<(usize, u32), ExportNote> = HashMap::new();
+
+    for (frame, &end) in recording.frames.iter().zip(&ends) {
+        let mut present: Vec<(usize, u32)> = Vec::new();
+        for (input, channel) in frame.channels.iter().enumerate() {
+            match config.source {
+                MidiSource::Pitch => {
+                    let pitch = channel.pitch
+                        .filter(|&(f0, confidence, db)| f0 > 0.0 && confidence >= config.min_confidence && db >= config.velocity_floor_db);
+                    let Some((f0, _, db)) = pitch else { continue };
+                    notes.extend(continue_note(&mut sounding, (input, 0), channel.onset, frame.time, end, midi_pitch(f0), db));
+                    present.push((input, 0));
+                }
+                MidiSource::Partials => {
+                    let mut loudest: Vec<&(u32, f32, f32)> = channel.partials.iter()
+                        .filter(|&&(_, _, db)| db >= config.velocity_floor_db)
+                        .collect();
+                    loudest.sort_by(|a, b| b.2.total_cmp(&a.2));
+                    for &&(track, freq, db) in loudest.iter().take(config.top_partials) {
+                        notes.extend(continue_note(&mut sounding, (input, track), channel.onset, frame.time, end, midi_pitch(freq), db));
+                        present.push((input, track));
+                    }
+                }
+            }
+        }
+        // Tracks missing from this frame have ended
+        let ended: Vec<(usize, u32)> = sounding.keys().filter(|key| !present.contains(key)).copied().collect();
+        notes.extend(ended.into_iter().filter_map(|key| sounding.remove(&key)));
+    }
+    notes.extend(sounding.into_values());
+    notes.retain(|note| (0.0..=127.0).contains(&note.pitches[0].1.round()));
+    notes.sort_by(|a, b| a.start.total_cmp(&b.start));
+    notes
+}
+
+/// Appends a MIDI variable-length quantity.
+fn push_vlq(out: &mut Vec<u8>, value: u32) {
+    let mut groups = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        groups.push(0x80 | (rest & 0x7F) as u8);
+        rest >>= 7;
+    }
+    out.extend(groups.iter().rev());
+}
+
+/// An MTrk chunk from events as (tick, order at equal ticks, bytes), ending the track
+/// after the last event.
+fn track_chunk(name: &str, mut events: Vec<(u64, u8, Vec<u8>)>) -> Vec<u8> {
+    events.sort_by_key(|&(tick, order, _)| (tick, order));
+    let mut data = Vec::new();
+    push_vlq(&mut data, 0);
+    data.extend([0xFF, 0x03]);
+    push_vlq(&mut data, name.len() as u32);
+    data.extend(name.as_bytes());
+
+    let mut last_tick = 0;
+    for (tick, _, bytes) in events {
+        push_vlq(&mut data, (tick - last_tick).min(u32::MAX as u64) as u32);
+        data.extend(bytes);
+        last_tick = tick;
+    }
+    data.extend([0x00, 0xFF, 0x2F, 0x00]);
+
+    let mut chunk = b"MTrk".to_vec();
+    chunk.extend((data.len() as u32).to_be_bytes());
+    chunk.extend(data);
+    chunk
+}
+
+/// Renders the recording as a type 1 Standard MIDI File: a tempo track, then a track per
+/// input channel. Notes follow the live MIDI settings (source, MPE, bend range, velocity
+/// range); each carries pitch bend through its glides where it has a channel to itself.
+pub fn export(recording: &SessionRecording, config: &MidiConfig, options: &ExportOptions) -> Vec<u8> {
+    let origin = recording.frames.first().map_or(0.0, |frame| frame.time);
+    let seconds_per_quarter = 60.0 / options.tempo_bpm.clamp(20.0, 400.0) as f64;
+    let ticks = |time: f64| ((time - origin).max(0.0) / seconds_per_quarter * TICKS_PER_QUARTER as f64).round() as u64;
+    let grid = (options.quantize_division > 0).then(|| seconds_per_quarter / options.quantize_division as f64);
+
+    let mut notes = collect_notes(recording, config);
+    notes.retain(|note| (note.end - note.start) * 1000.0 >= options.min_duration_ms as f64);
+    if let Some(grid) = grid {
+        for note in notes.iter_mut() {
+            let start = origin + ((note.start - origin) / grid).round() * grid;
+            let end = (origin + ((note.end - origin) / grid).round() * grid).max(start + grid);
+            let shift = start - note.start;
+            for point in note.pitches.iter_mut() {
+                point.0 = (point.0 + shift).min(end);
+            }
+            note.start = start;
+            note.end = end;
+        }
+    }
+
+    let num_inputs = recording.frames.iter().map(|frame| frame.channels.len()).max().unwrap_or(0);
+    let mut tracks: Vec<Vec<(u64, u8, Vec<u8>)>> = vec![Vec::new(); num_inputs.max(1)];
+    tracks[0].extend(setup_messages(config).into_iter().map(|message| (0, 0, message.to_vec())));
+
+    // Where each note lands as (channel, start tick, end tick), in start order. With MPE
+    // each note takes the member channel that has been free longest, or steals the one
+    // whose note ends soonest, cutting that note off; otherwise notes share their input's
+    // channel, so a bent note holds the channel to itself and other notes wait for their
+    // key to be free, as the live output does
+    let mut placed: Vec<(&ExportNote, u8, u64, u64)> = Vec::new();
+    let mut channel_free_at: Vec<(u8, u64)> = MPE_MEMBER_CHANNELS.map(|channel| (channel, 0)).collect();
+    let mut last_on_channel: HashMap<u8, usize> = HashMap::new();
+    let mut key_free_at: HashMap<(u8, u8), u64> = HashMap::new();
+    let mut unmapped = 0;
+    for note in &notes {
+        let start = ticks(note.start);
+        let end = ticks(note.end).max(start + 1);
+        if config.mpe {
+            let slot = channel_free_at.iter_mut()
+                .min_by_key(|&&mut (_, free_at)| (free_at > start, free_at))
+                .expect("MPE zone has member channels");
+            if let Some(&previous) = last_on_channel.get(&slot.0) {
+                placed[previous].3 = placed[previous].3.min(start);
+            }
+            slot.1 = end;
+            last_on_channel.insert(slot.0, placed.len());
+            placed.push((note, slot.0, start, end));
+            continue;
+        }
+        let Some(channel) = config.input_channel(note.input) else {
+            unmapped += 1;
+            continue;
+        };
+        if config.bends_notes() {
+            // A bend would move any other note on the channel, so the previous one ends here
+            if let Some(&previous) = last_on_channel.get(&channel) {
+                placed[previous].3 = placed[previous].3.min(start);
+            }
+            last_on_channel.insert(channel, placed.len());
+            placed.push((note, channel, start, end));
+        } else {
+            let free_at = key_free_at.entry((channel, note.key())).or_insert(0);
+            let start = start.max(*free_at);
+            if start < end {
+                *free_at = end;
+                placed.push((note, channel, start, end));
+            }
+        }
+    }
+    if unmapped > 0 {
+        warn!(target: "midi", "Left out {} notes from input channels past MIDI channel 16; lower the first channel or use MPE", unmapped);
+    }
+
+    let mut exported = 0;
+    for &(note, channel, start, end) in placed.iter().filter(|&&(_, _, start, end)| end > start) {
+        let key = note.key();
+        let events = &mut tracks[note.input.min(num_inputs.saturating_sub(1))];
+
+        if config.bends_notes() {
+            let mut last_bend = None;
+            for &(time, pitch) in &note.pitches {
+                let tick = if last_bend.is_none() { start } else { ticks(time).clamp(start, end - 1) };
+                let bend = bend_value(pitch - key as f32, config.bend_range);
+                if last_bend.map_or(true, |last: i32| (bend - last).abs() >= BEND_DEADBAND) {
+                    events.push((tick, 1, vec![0xE0 | channel, (bend & 0x7F) as u8, ((bend >> 7) & 0x7F) as u8]));
+                    last_bend = Some(bend);
+                }
+            }
+        }
+        let note_velocity = velocity(note.peak_db, config.velocity_floor_db, config.velocity_ceiling_db);
+        events.push((start, 2, vec![0x90 | channel, key, note_velocity.clamp(1, 127)]));
+        events.push((end, 0, vec![0x80 | channel, key, 0]));
+        exported += 1;
+    }
+
+    let tempo = (seconds_per_quarter * 1_000_000.0).round() as u32;
+    let tempo_track = track_chunk("Tempo", vec![(0, 0, vec![0xFF, 0x51, 0x03, (tempo >> 16) as u8, (tempo >> 8) as u8, tempo as u8])]);
+
+    let mut file = b"MThd".to_vec();
+    file.extend(6u32.to_be_bytes());
+    file.extend(1u16.to_be_bytes());
+    file.extend((tracks.len() as u16 + 1).to_be_bytes());
+    file.extend(TICKS_PER_QUARTER.to_be_bytes());
+    file.extend(tempo_track);
+    for (input, events) in tracks.into_iter().enumerate() {
+        file.extend(track_chunk(&format!("Channel {}", input + 1), events));
+    }
+    info!(target: "midi", "Exported {} notes over {:.1}s", exported, recording.duration());
+    file
+}
+
+/// Writes the exported recording to `path`.
+pub fn save(recording: &SessionRecording, config: &MidiConfig, options: &ExportOptions, path: &str) -> Result<()> {
+    fs::write(path, export(recording, config, options))?;
+    info!("MIDI file saved to {}", path);
+    Ok(())
+}
+
+/// Feeds every analysis frame into the shared recording while it is recording.
+pub async fn recorder_loop(
+    mut partials_rx: broadcast::Receiver<PartialsData>,
+    mut analysis_rx: broadcast::Receiver<AnalysisData>,
+    recording: Arc<Mutex<SessionRecording>>,
+    shutdown_flag: Arc<AtomicBool>,
+) {
+    let mut latest_analysis: AnalysisData = Vec::new();
+
+    while !shutdown_flag.load(Ordering::Relaxed) {
+        let partials = match partials_rx.recv().await {
+            Ok(partials) => partials,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!(target: "midi", "Recorder partials receiver lagged by {} messages.", n);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                info!(target: "midi", "Partials broadcast channel closed for recording.");
+                break;
+            }
+        };
+        // A frame's analysis is published just before its partials
+        loop {
+            match analysis_rx.try_recv() {
+                Ok(analysis) => latest_analysis = analysis,
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+
+        let captured_at = latest_analysis.first()
+            .and_then(|channel| channel.timing)
+            .map_or_else(Instant::now, |timing| timing.captured_at);
+        if let Ok(mut recording) = recording.lock() {
+            recording.push(captured_at, &partials, &latest_analysis);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::fft_analysis::ChannelAnalysis;
+
+    fn vlq(value: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        push_vlq(&mut out, value);
+        out
+    }
+
+    /// Track chunks of a file as (offset of their data, data length).
+    fn track_chunks(file: &[u8]) -> Vec<(usize, usize)> {
+        let mut chunks = Vec::new();
+        let mut offset = 14;
+        while offset + 8 <= file.len() {
+            assert_eq!(&file[offset..offset + 4], b"MTrk");
+            let len = u32::from_be_bytes(file[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            chunks.push((offset + 8, len));
+            offset += 8 + len;
+        }
+        assert_eq!(offset, file.len());
+        chunks
+    }
+
+    /// Records `frames` frames 10 ms apart of one input whose partials are given per frame
+    /// as (track ID, Hz, dB).
+    fn recording(frames: usize, partials: impl Fn(usize) -> Vec<(u32, f32, f32)>) -> SessionRecording {
+        let mut recording = SessionRecording::default();
+        recording.start();
+        let start = Instant::now();
+        for frame in 0..frames {
+            let frame_partials = partials(frame);
+            let analysis = vec![ChannelAnalysis {
+                tracks: frame_partials.iter().map(|&(track, _, _)| Some(track)).collect(),
+                ..Default::default()
+            }];
+            let partials = vec![frame_partials.iter().map(|&(_, freq, db)| (freq, db)).collect()];
+            recording.push(start + Duration::from_millis(10 * frame as u64), &partials, &analysis);
+        }
+        recording
+    }
+
+    #[test]
+    fn vlq_boundaries() {
+        assert_eq!(vlq(0), [0x00]);
+        assert_eq!(vlq(0x7F), [0x7F]);
+        assert_eq!(vlq(0x80), [0x81, 0x00]);
+        assert_eq!(vlq(0x3FFF), [0xFF, 0x7F]);
+        assert_eq!(vlq(0x4000), [0x81, 0x80, 0x00]);
+    }
+
+    #[test]
+    fn track_chunk_ends_with_end_of_track() {
+        let chunk = track_chunk("A", vec![(0x80, 0, vec![0x90, 60, 100])]);
+        assert_eq!(&chunk[..4], b"MTrk");
+        let len = u32::from_be_bytes(chunk[4..8].try_into().unwrap()) as usize;
+        assert_eq!(chunk.len(), 8 + len);
+        assert_eq!(&chunk[8..12], [0x00, 0xFF, 0x03, 0x01]);
+        assert_eq!(&chunk[13..18], [0x81, 0x00, 0x90, 60, 100]);
+        assert_eq!(&chunk[chunk.len() - 4..], [0x00, 0xFF, 0x2F, 0x00]);
+    }
+
+    #[test]
+    fn header_and_track_count() {
+        let file = export(&SessionRecording::default(), &MidiConfig::default(), &ExportOptions::default());
+        assert_eq!(&file[..4], b"MThd");
+        assert_eq!(&file[4..14], [0, 0, 0, 6, 0, 1, 0, 2, 0x01, 0xE0]);
+        assert_eq!(track_chunks(&file).len(), 2);
+
+        let two_inputs = {
+            let mut recording = SessionRecording::default();
+            recording.start();
+            recording.push(Instant::now(), &vec![Vec::new(); 2], &vec![ChannelAnalysis::default(); 2]);
+            recording
+        };
+        let file = export(&two_inputs, &MidiConfig::default(), &ExportOptions::default());
+        assert_eq!(&file[10..12], [0, 3]);
+        assert_eq!(track_chunks(&file).len(), 3);
+    }
+
+    #[test]
+    fn partials_on_one_key_do_not_overlap() {
+        // Two tracks a few cents apart share a key for the whole recording
+        let recording = recording(20, |_| vec![(1, 440.0, -10.0), (2, 441.0, -20.0)]);
+        let config = MidiConfig { source: MidiSource::Partials, ..Default::default() };
+        let file = export(&recording, &config, &ExportOptions { min_duration_ms: 0.0, ..Default::default() });
+
+        let (data, len) = track_chunks(&file)[1];
+        let note_ons = data_events(&file[data..data + len]).iter().filter(|event| event[0] & 0xF0 == 0x90).count();
+        assert_eq!(note_ons, 1);
+    }
+
+    #[test]
+    fn gliding_partial_retriggers_past_the_retrigger_interval() {
+        // One track that glides up two semitones halfway through
+        let recording = recording(20, |frame| vec![(1, if frame < 10 { 440.0 } else { 493.9 }, -10.0)]);
+        let config = MidiConfig { source: MidiSource::Partials, ..Default::default() };
+        let file = export(&recording, &config, &ExportOptions { min_duration_ms: 0.0, ..Default::default() });
+
+        let (data, len) = track_chunks(&file)[1];
+        let keys: Vec<u8> = data_events(&file[data..data + len]).iter()
+            .filter(|event| event[0] & 0xF0 == 0x90)
+            .map(|event| event[1])
+            .collect();
+        assert_eq!(keys, [69, 71]);
+    }
+
+    #[test]
+    fn mpe_steals_the_channel_ending_soonest() {
+        // Fifteen partials fill every member channel, then a sixteenth joins them
+        let recording = recording(20, |frame| {
+            let tracks = if frame < 10 { 15 } else { 16 };
+            (1..=tracks).map(|track| (track, 100.0 * track as f32, -10.0)).collect()
+        });
+        let config = MidiConfig { source: MidiSource::Partials, top_partials: 16, mpe: true, ..Default::default() };
+        let file = export(&recording, &config, &ExportOptions { min_duration_ms: 0.0, ..Default::default() });
+
+        let (data, len) = track_chunks(&file)[1];
+        let mut sounding: HashMap<u8, u8> = HashMap::new();
+        let mut note_ons = 0;
+        for (_, event) in timed_events(&file[data..data + len]) {
+            let channel = event[0] & 0x0F;
+            match event[0] & 0xF0 {
+                0x90 => {
+                    assert!(sounding.insert(channel, event[1]).is_none(), "two notes on channel {}", channel);
+                    note_ons += 1;
+                }
+                0x80 => assert_eq!(sounding.remove(&channel), Some(event[1])),
+                _ => {}
+            }
+        }
+        assert_eq!(note_ons, 16);
+        assert!(sounding.is_empty());
+    }
+
+    /// Channel messages in a track's data, skipping meta events.
+    fn data_events(data: &[u8]) -> Vec<[u8; 3]> {
+        timed_events(data).into_iter().map(|(_, event)| event).collect()
+    }
+
+    /// Channel messages in a track's data with their absolute ticks, skipping meta events.
+    fn timed_events(data: &[u8]) -> Vec<(u64, [u8; 3])> {
+        let mut events = Vec::new();
+        let (mut offset, mut tick) = (0, 0u64);
+        while offset < data.len() {
+            let mut delta = 0u64;
+            loop {
+                delta = (delta << 7) | (data[offset] & 0x7F) as u64;
+                offset += 1;
+                if data[offset - 1] & 0x80 == 0 {
+                    break;
+                }
+            }
+            tick += delta;
+            if data[offset] == 0xFF {
+                offset += 3 + data[offset + 2] as usize;
+            } else {
+                events.push((tick, [data[offset], data[offset + 1], data[offset + 2]]));
+                offset += 3;
+            }
+        }
+        events
+    }
+}
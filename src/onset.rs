@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Detection function values the adaptive threshold is taken over
+const THRESHOLD_WINDOW: usize = 16;
+// Share of the window mean added to the scaled median, so a flat detection function never fires
+const MEAN_WEIGHT: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum OnsetMethod {
+    /// Rectified magnitude increase since the previous frame
+    #[default]
+    SpectralFlux,
+    /// Bin-weighted spectral energy, emphasising percussive high-frequency content
+    HighFrequencyContent,
+}
+
+impl OnsetMethod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            OnsetMethod::SpectralFlux => "Spectral Flux",
+            OnsetMethod::HighFrequencyContent => "High-Frequency Content",
+        }
+    }
+}
+
+/// Settings for per-channel onset detection.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnsetConfig {
+    pub enabled: bool,
+    pub method: OnsetMethod,
+    pub threshold: f32,       // Multiple of the recent median a peak must exceed
+    pub min_interval_ms: f32, // Shortest time between two onsets on one channel
+    pub hard_boundaries: bool, // End every partial track at an onset
+}
+
+impl Default for OnsetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            method: OnsetMethod::SpectralFlux,
+            threshold: 1.5,
+            min_interval_ms: 50.0,
+            hard_boundaries: false,
+        }
+    }
+}
+
+/// A detected onset, timed in seconds since the analyzer started.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct OnsetEvent {
+    pub time: f64,
+    /// Detection function value at the onset
+    pub strength: f32,
+}
+
+/// Value of the onset detection function for one frame, given its linear magnitude
+/// spectrum and the spectral flux already computed for the frame descriptors.
+pub fn detection_value(method: OnsetMethod, magnitudes: &[f32], flux: f32) -> f32 {
+    match method {
+        OnsetMethod::SpectralFlux => flux,
+        OnsetMethod::HighFrequencyContent => {
+            if magnitudes.is_empty() {
+                return 0.0;
+            }
+            let bins = magnitudes.len() as f32;
+            magnitudes.iter()
+                .enumerate()
+                .map(|(k, m)| k as f32 / bins * m * m)
+                .sum()
+        }
+    }
+}
+
+/// Adaptive-threshold peak picker over one channel's detection function.
+///
+/// A frame is an onset when its value is a local maximum and exceeds the scaled
+/// median of the recent values. Because the maximum is only known once the next
+/// frame arrives, onsets are reported one frame late with the peak frame's time.
+#[derive(Debug, Clone, Default)]
+pub struct OnsetDetector {
+    history: VecDeque<f32>,
+    /// The previous frame, which may still turn out to be a peak
+    candidate: Option<(f64, f32)>,
+    last_onset: Option<OnsetEvent>,
+}
+
+impl OnsetDetector {
+    pub fn process(&mut self, time: f64, value: f32, config: &OnsetConfig) -> Option<OnsetEvent> {
+        let mut onset = None;
+        if let Some((candidate_time, candidate_value)) = self.candidate {
+            let rising = self.history.iter().rev().nth(1).map_or(true, |&before| candidate_value > before);
+            let clear_of_last = self.last_onset
+                .map_or(true, |last| (candidate_time - last.time) * 1000.0 >= config.min_interval_ms as f64);
+            if rising && candidate_value >= value && clear_of_last && candidate_value > self.threshold(config.threshold) {
+                onset = Some(OnsetEvent { time: candidate_time, strength: candidate_value });
+                self.last_onset = onset;
+            }
+        }
+
+        self.history.push_back(value);
+        while self.history.len() > THRESHOLD_WINDOW {
+            self.history.pop_front();
+        }
+        self.candidate = Some((time, value));
+        onset
+    }
+
+    /// Most recent onset reported by `process`.
+    pub fn last_onset(&self) -> Option<OnsetEvent> {
+        self.last_onset
+    }
+
+    fn threshold(&self, multiplier: f32) -> f32 {
+        if self.history.is_empty() {
+            return f32::INFINITY;
+        }
+        let mut sorted: Vec<f32> = self.history.iter().copied().collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let median = sorted[sorted.len() / 2];
+        let mean = sorted.iter().sum::<f32>() / sorted.len() as f32;
+        multiplier * median + MEAN_WEIGHT * mean
+    }
+}
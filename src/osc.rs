@@ -0,0 +1,89 @@
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::{debug, error, info, warn};
+use tokio::sync::broadcast;
+use crate::fft_analysis::AnalysisData;
+
+/// One argument of an OSC message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+}
+
+/// Appends an OSC string: the bytes, a terminating NUL, then NUL padding to a multiple of four.
+fn push_osc_string(packet: &mut Vec<u8>, value: &str) {
+    packet.extend_from_slice(value.as_bytes());
+    packet.push(0);
+    while packet.len() % 4 != 0 {
+        packet.push(0);
+    }
+}
+
+/// Encodes an OSC 1.0 message with int32 and float32 arguments.
+pub fn encode_message(address: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    push_osc_string(&mut packet, address);
+
+    let type_tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            OscArg::Int(_) => 'i',
+            OscArg::Float(_) => 'f',
+        }))
+        .collect();
+    push_osc_string(&mut packet, &type_tags);
+
+    for arg in args {
+        match arg {
+            OscArg::Int(value) => packet.extend_from_slice(&value.to_be_bytes()),
+            OscArg::Float(value) => packet.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+    packet
+}
+
+/// Sends every detected onset to `target` as `/onset <channel> <time> <strength>`,
+/// with the channel as an int (0-based) and the time in seconds since start.
+pub async fn onset_sender_loop(
+    mut analysis_rx: broadcast::Receiver<AnalysisData>,
+    target: String,
+    shutdown_flag: Arc<AtomicBool>,
+) {
+    let socket = match UdpSocket::bind("0.0.0.0:0") {
+        Ok(socket) => socket,
+        Err(e) => {
+            error!(target: "osc", "Failed to open UDP socket for OSC output: {}", e);
+            return;
+        }
+    };
+    info!(target: "osc", "Sending onsets as OSC to {}", target);
+
+    while !shutdown_flag.load(Ordering::Relaxed) {
+        match analysis_rx.recv().await {
+            Ok(analysis) => {
+                for (channel, channel_analysis) in analysis.iter().enumerate() {
+                    if let Some(onset) = channel_analysis.onset {
+                        let packet = encode_message("/onset", &[
+                            OscArg::Int(channel as i32),
+                            OscArg::Float(onset.time as f32),
+                            OscArg::Float(onset.strength),
+                        ]);
+                        match socket.send_to(&packet, &target) {
+                            Ok(_) => debug!(target: "osc", "Sent onset for channel {} at {:.3}s", channel, onset.time),
+                            Err(e) => warn!(target: "osc", "Failed to send OSC onset to {}: {}", target, e),
+                        }
+                    }
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!(target: "osc", "OSC analysis receiver lagged by {} messages.", n);
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                info!(target: "osc", "Analysis broadcast channel closed for OSC output.");
+                break;
+            }
+        }
+    }
+    info!(target: "osc", "OSC onset sender shutting down.");
+}
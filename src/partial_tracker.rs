@@ -0,0 +1,59 @@
+// Furthest a partial may move between frames and still continue the same track
+const TRACK_TOLERANCE_CENTS: f32 = 50.0;
+
+/// Links each channel's partials across frames into tracks with stable IDs.
+///
+/// IDs start at 1 so shared memory consumers can read 0 as "no partial".
+#[derive(Debug, Clone)]
+pub struct PartialTracker {
+    /// Live tracks per channel as (ID, frequency in the last frame)
+    tracks: Vec<Vec<(u32, f32)>>,
+    next_id: u32,
+}
+
+impl Default for PartialTracker {
+    fn default() -> Self {
+        Self { tracks: Vec::new(), next_id: 1 }
+    }
+}
+
+impl PartialTracker {
+    /// Assigns a track ID to every non-empty partial of a channel, continuing the nearest
+    /// track from the previous frame where one is close enough. Louder partials pick first.
+    /// With `boundary` set every live track ends and all partials start new ones.
+    pub fn update(&mut self, channel: usize, partials: &[(f32, f32)], boundary: bool) -> Vec<Option<u32>> {
+        if self.tracks.len() <= channel {
+            self.tracks.resize(channel + 1, Vec::new());
+        }
+        let mut previous = if boundary { Vec::new() } else { std::mem::take(&mut self.tracks[channel]) };
+
+        let mut order: Vec<usize> = (0..partials.len())
+            .filter(|&i| partials[i].0 > 0.0)
+            .collect();
+        order.sort_by(|&a, &b| partials[b].1.partial_cmp(&partials[a].1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut ids = vec![None; partials.len()];
+        let mut live = Vec::with_capacity(order.len());
+        for i in order {
+            let freq = partials[i].0;
+            let nearest = previous.iter()
+                .enumerate()
+                .map(|(index, &(_, prev_freq))| (index, (1200.0 * (freq / prev_freq).log2()).abs()))
+                .filter(|&(_, cents)| cents < TRACK_TOLERANCE_CENTS)
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+            let id = match nearest {
+                Some((index, _)) => previous.swap_remove(index).0,
+                None => {
+                    let id = self.next_id;
+                    self.next_id = self.next_id.wrapping_add(1).max(1);
+                    id
+                }
+            };
+            ids[i] = Some(id);
+            live.push((id, freq));
+        }
+
+        self.tracks[channel] = live;
+        ids
+    }
+}
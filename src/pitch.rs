@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use realfft::RealFftPlanner;
+
+// YIN absolute threshold: the first dip of the normalised difference below this is taken as the period
+const YIN_THRESHOLD: f32 = 0.15;
+// Longest stretch of signal the difference function is integrated over
+const MAX_INTEGRATION_WINDOW: usize = 2048;
+
+/// Fundamental frequency estimate for one channel.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PitchEstimate {
+    pub f0: f32,
+    /// 0.0 (no periodicity) to 1.0 (perfectly periodic)
+    pub confidence: f32,
+}
+
+/// Estimates the fundamental frequency of `signal` with the YIN algorithm.
+///
+/// Only periods corresponding to `min_freq..=max_freq` are searched. Returns `None`
+/// when the buffer is too short for the requested range or the signal is silent.
+pub fn estimate_pitch(signal: &[f32], sample_rate: u32, min_freq: f32, max_freq: f32) -> Option<PitchEstimate> {
+    let sample_rate = sample_rate as f32;
+    let min_freq = min_freq.max(20.0);
+    let max_freq = max_freq.min(sample_rate / 4.0);
+    if min_freq >= max_freq {
+        return None;
+    }
+
+    let tau_min = ((sample_rate / max_freq).floor() as usize).max(2);
+    let tau_max = (sample_rate / min_freq).ceil() as usize;
+    if signal.len() <= tau_max + 2 {
+        return None;
+    }
+    let window = (signal.len() - tau_max - 1).min(MAX_INTEGRATION_WINDOW);
+    if window < tau_min || signal.iter().all(|&s| s == 0.0) {
+        return None;
+    }
+
+    // Difference function d(tau) and its cumulative mean normalised form d'(tau)
+    let differences = difference_function(&signal[..window + tau_max + 1], window)?;
+    let mut normalised = vec![1.0f32; tau_max + 2];
+    let mut running_sum = 0.0f32;
+    for tau in 1..=tau_max + 1 {
+        let difference = differences[tau];
+        running_sum += difference;
+        normalised[tau] = if running_sum > 0.0 {
+            difference * tau as f32 / running_sum
+        } else {
+            1.0
+        };
+    }
+
+    // First dip below the threshold, followed down to its local minimum;
+    // otherwise fall back to the global minimum of the searched range
+    let tau = match (tau_min..=tau_max).find(|&tau| normalised[tau] < YIN_THRESHOLD) {
+        Some(mut tau) => {
+            while tau < tau_max && normalised[tau + 1] < normalised[tau] {
+                tau += 1;
+            }
+            tau
+        }
+        None => (tau_min..=tau_max)
+            .min_by(|&a, &b| normalised[a].partial_cmp(&normalised[b]).unwrap_or(std::cmp::Ordering::Equal))?,
+    };
+
+    // Parabolic interpolation around the chosen lag
+    let (left, centre, right) = (normalised[tau - 1], normalised[tau], normalised[tau + 1]);
+    let denominator = left - 2.0 * centre + right;
+    let offset = if denominator.abs() > f32::EPSILON {
+        (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+
+    Some(PitchEstimate {
+        f0: sample_rate / (tau as f32 + offset),
+        confidence: (1.0 - centre).clamp(0.0, 1.0),
+    })
+}
+
+/// YIN difference function d(tau) = sum over `window` samples of (x[j] - x[j + tau])^2,
+/// for every lag up to `signal.len() - window`. Expanded into the two energies minus
+/// twice the cross-correlation, which is taken with one FFT, so the cost grows as
+/// N log N instead of with lags times window.
+fn difference_function(signal: &[f32], window: usize) -> Option<Vec<f32>> {
+    let lags = signal.len() - window + 1;
+    let size = signal.len().next_power_of_two();
+    let mut planner = RealFftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+
+    let mut head = vec![0.0f32; size];
+    head[..window].copy_from_slice(&signal[..window]);
+    let mut whole = vec![0.0f32; size];
+    whole[..signal.len()].copy_from_slice(signal);
+    let mut head_spectrum = forward.make_output_vec();
+    let mut whole_spectrum = forward.make_output_vec();
+    forward.process(&mut head, &mut head_spectrum).ok()?;
+    forward.process(&mut whole, &mut whole_spectrum).ok()?;
+
+    // Cross-correlation r(tau) = sum of x[j] * x[j + tau] over the window
+    for (h, w) in head_spectrum.iter().zip(whole_spectrum.iter_mut()) {
+        *w *= h.conj();
+    }
+    // The DC and Nyquist bins of a real signal's spectrum are real
+    whole_spectrum[0].im = 0.0;
+    whole_spectrum[size / 2].im = 0.0;
+    let mut correlation = inverse.make_output_vec();
+    inverse.process(&mut whole_spectrum, &mut correlation).ok()?;
+    let scale = 1.0 / size as f32;
+
+    // Energy of the window starting at each lag, from running sums of squares
+    let mut squares = Vec::with_capacity(signal.len() + 1);
+    squares.push(0.0f64);
+    for &s in signal {
+        squares.push(squares[squares.len() - 1] + (s * s) as f64);
+    }
+    let energy = |start: usize| (squares[start + window] - squares[start]) as f32;
+
+    Some((0..lags)
+        .map(|tau| (energy(0) + energy(tau) - 2.0 * correlation[tau] * scale).max(0.0))
+        .collect())
+}
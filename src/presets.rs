@@ -5,8 +5,17 @@ use std::path::Path;
 use anyhow::{Result, anyhow};
 use log::{info, error, warn};
 
-use crate::fft_analysis::{FFTConfig, WindowType};
+use crate::fft_analysis::{FFTConfig, WindowType, ChannelOverrides, CrosstalkMode, AdaptiveCrosstalkConfig};
 use crate::resynth::{ResynthConfig, DEFAULT_UPDATE_RATE};
+use crate::tuning::TuningConfig;
+use crate::onset::OnsetConfig;
+use crate::averaging::AveragingConfig;
+use crate::waterfall::WaterfallConfig;
+use crate::plot::ChannelDisplay;
+use crate::routing::OutputRouting;
+use crate::transforms::PartialTransform;
+use crate::limiter::LimiterConfig;
+use crate::midi::MidiConfig;
 
 // A single preset containing all configurable GUI values
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -18,6 +27,8 @@ pub struct Preset {
     pub min_freq_spacing: f64,
     pub window_type: WindowType,
     pub crosstalk_enabled: bool,
+    #[serde(default)]
+    pub crosstalk_mode: CrosstalkMode,
     pub crosstalk_threshold: f32,
     pub crosstalk_reduction: f32,
     pub harmonic_tolerance: f32,
@@ -25,11 +36,43 @@ pub struct Preset {
     pub root_freq_max: f32,
     pub freq_match_distance: f32,
     pub fft_gain: f32,
+    #[serde(default)]
+    pub channel_overrides: Vec<ChannelOverrides>,
+    #[serde(default)]
+    pub adaptive_crosstalk: AdaptiveCrosstalkConfig,
+    #[serde(default = "default_pitch_confidence_threshold")]
+    pub pitch_confidence_threshold: f32,
+    #[serde(default)]
+    pub onset: OnsetConfig,
+    #[serde(default)]
+    pub averaging: AveragingConfig,
 
     // ResynthConfig fields
     pub gain: f32,
     pub freq_scale: f32,
     pub update_rate: f32,
+    #[serde(default)]
+    pub onset_boundaries: bool,
+    #[serde(default)]
+    pub routing: OutputRouting,
+    #[serde(default)]
+    pub noise_level: f32,
+    #[serde(default)]
+    pub transpose_semitones: f32,
+    #[serde(default)]
+    pub transpose_cents: f32,
+    #[serde(default)]
+    pub preserve_formants: bool,
+    #[serde(default)]
+    pub transforms: Vec<PartialTransform>,
+    #[serde(default)]
+    pub low_latency: bool,
+    #[serde(default)]
+    pub limiter: LimiterConfig,
+    #[serde(default)]
+    pub midi: MidiConfig,
+    #[serde(default)]
+    pub mute_live_while_frozen: bool,
 
     // MyApp display fields
     pub y_scale: f32,
@@ -38,10 +81,37 @@ pub struct Preset {
     pub show_line_plot: bool,
     pub show_spectrograph: bool,
     pub show_results: bool,
+    #[serde(default)]
+    pub show_notes: bool,
+    #[serde(default)]
+    pub show_note_grid: bool,
+    #[serde(default)]
+    pub tuning: TuningConfig,
+    #[serde(default)]
+    pub show_descriptors: bool,
+    #[serde(default)]
+    pub selected_descriptor: usize,
+    #[serde(default)]
+    pub log_frequency: bool,
+    #[serde(default)]
+    pub show_cursors: bool,
+    #[serde(default)]
+    pub snap_cursors: bool,
+    #[serde(default)]
+    pub waterfall: WaterfallConfig,
+    #[serde(default)]
+    pub channel_display: Vec<ChannelDisplay>,
+    #[serde(default)]
+    pub resynth_follows_channels: bool,
     pub buffer_size: usize,
     // Note: buffer_size is handled separately and not part of a preset
 }
 
+// Presets saved before pitch-based roots existed fall back to the FFTConfig default
+fn default_pitch_confidence_threshold() -> f32 {
+    FFTConfig::default().pitch_confidence_threshold
+}
+
 // Manages loading, saving, and holding presets
 pub struct PresetManager {
     pub presets: BTreeMap<String, Preset>,
@@ -98,6 +168,7 @@ impl PresetManager {
             min_freq_spacing: fft_config.min_freq_spacing,
             window_type: fft_config.window_type,
             crosstalk_enabled: fft_config.crosstalk_enabled,
+            crosstalk_mode: fft_config.crosstalk_mode,
             crosstalk_threshold: fft_config.crosstalk_threshold,
             crosstalk_reduction: fft_config.crosstalk_reduction,
             harmonic_tolerance: fft_config.harmonic_tolerance,
@@ -105,19 +176,46 @@ impl PresetManager {
             root_freq_max: fft_config.root_freq_max,
             freq_match_distance: fft_config.freq_match_distance,
             fft_gain: fft_config.gain,
+            channel_overrides: Vec::new(),
+            adaptive_crosstalk: AdaptiveCrosstalkConfig::default(),
+            pitch_confidence_threshold: fft_config.pitch_confidence_threshold,
+            onset: OnsetConfig::default(),
+            averaging: AveragingConfig::default(),
 
             // ResynthConfig fields
             gain: 0.5,
             freq_scale: 1.0,
             update_rate: DEFAULT_UPDATE_RATE,
+            onset_boundaries: false,
+            routing: OutputRouting::default(),
+            noise_level: 0.0,
+            transpose_semitones: 0.0,
+            transpose_cents: 0.0,
+            preserve_formants: false,
+            transforms: Vec::new(),
+            low_latency: false,
+            limiter: LimiterConfig::default(),
+            midi: MidiConfig::default(),
+            mute_live_while_frozen: false,
 
             // MyApp display fields
-            y_scale: 80.0,
+            y_scale: 100.0,
             alpha: 255,
             bar_width: 5.0,
             show_line_plot: false,
             show_spectrograph: false,
             show_results: true,
+            show_notes: false,
+            show_note_grid: false,
+            tuning: TuningConfig::default(),
+            show_descriptors: false,
+            selected_descriptor: 0,
+            log_frequency: false,
+            show_cursors: false,
+            snap_cursors: false,
+            waterfall: WaterfallConfig::default(),
+            channel_display: Vec::new(),
+            resynth_follows_channels: false,
             buffer_size: crate::DEFAULT_BUFFER_SIZE,
         }
     }
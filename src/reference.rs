@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use anyhow::{Result, anyhow};
+use log::info;
+
+/// A captured per-channel spectrum kept for comparison against the live analysis.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceTrace {
+    pub name: String,
+    pub captured_at: String,
+    /// Line spectrum per channel as (Hz, dBFS)
+    pub line_data: Vec<Vec<(f32, f32)>>,
+    /// Partials per channel as (Hz, dBFS)
+    pub partials: Vec<Vec<(f32, f32)>>,
+}
+
+impl ReferenceTrace {
+    pub fn capture(name: &str, line_data: &[Vec<(f32, f32)>], partials: &[Vec<(f32, f32)>]) -> Self {
+        Self {
+            name: name.to_string(),
+            captured_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            line_data: line_data.to_vec(),
+            partials: partials.to_vec(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let yaml_str = fs::read_to_string(path)?;
+        serde_yaml::from_str(&yaml_str)
+            .map_err(|e| anyhow!("Failed to parse {}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let yaml_str = serde_yaml::to_string(self)?;
+        fs::write(path, yaml_str)?;
+        info!("Reference trace '{}' saved to {}", self.name, path);
+        Ok(())
+    }
+
+    /// Live minus reference in dB for each live bin, per channel. Channels the reference
+    /// doesn't have are left empty; bins are matched by frequency, so a reference taken at
+    /// another buffer size or window still lines up.
+    pub fn difference(&self, live: &[Vec<(f32, f32)>]) -> Vec<Vec<(f32, f32)>> {
+        live.iter()
+            .enumerate()
+            .map(|(channel, line)| match self.line_data.get(channel) {
+                Some(reference) if reference.len() >= 2 => line.iter()
+                    .map(|&(freq, db)| (freq, db - interpolate_db(reference, freq)))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+}
+
+/// Linearly interpolated dB of an evenly spaced line spectrum at `freq`.
+fn interpolate_db(line: &[(f32, f32)], freq: f32) -> f32 {
+    let freq_step = line[1].0 - line[0].0;
+    if freq_step <= 0.0 {
+        return line[0].1;
+    }
+    let position = ((freq - line[0].0) / freq_step).clamp(0.0, (line.len() - 1) as f32);
+    let index = (position as usize).min(line.len() - 2);
+    let fraction = position - index as f32;
+    line[index].1 + (line[index + 1].1 - line[index].1) * fraction
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use realfft::{ComplexToReal, RealFftPlanner};
+use realfft::num_complex::Complex;
+
+// Log-spaced bands the residual envelope is measured in
+const NOISE_BANDS: usize = 24;
+// Lowest band edge in Hz, below which residual energy is mostly hum and rumble
+const MIN_NOISE_FREQ: f32 = 40.0;
+// Bins either side of a partial's peak that belong to it (the window's main lobe)
+const PARTIAL_HALF_WIDTH_BINS: usize = 3;
+// Length of the noise blocks overlap-added at half a block apart
+const NOISE_BLOCK_LEN: usize = 2048;
+
+/// Spectral envelope of what is left of a channel once its partials are taken out: the
+/// noise half of a sines-plus-noise model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoiseEnvelope {
+    /// Band centre in Hz and mean residual power per bin in dBFS, lowest band first
+    pub bands: Vec<(f32, f32)>,
+    /// Residual power in the analysis range relative to a full-scale sine's, 0 for silence
+    #[serde(default)]
+    pub residual_energy: f32,
+}
+
+impl NoiseEnvelope {
+    /// Measures the residual of a line spectrum (Hz, dBFS, evenly spaced from DC) between
+    /// `min_freq` and `max_freq`, leaving out the bins around each partial.
+    pub fn compute(line_data: &[(f32, f32)], partials: &[(f32, f32)], min_freq: f32, max_freq: f32) -> Self {
+        if line_data.len() < 2 {
+            return Self::default();
+        }
+        let freq_step = line_data[1].0 - line_data[0].0;
+        let low_edge = min_freq.max(MIN_NOISE_FREQ);
+        let high_edge = max_freq.min(line_data[line_data.len() - 1].0);
+        if freq_step <= 0.0 || high_edge <= low_edge {
+            return Self::default();
+        }
+
+        let last_bin = line_data.len() - 1;
+        let mut is_partial = vec![false; line_data.len()];
+        for &(freq, _) in partials.iter().filter(|(freq, _)| *freq > 0.0) {
+            let centre = (freq / freq_step).round() as usize;
+            for flag in is_partial.iter_mut()
+                .take((centre + PARTIAL_HALF_WIDTH_BINS).min(last_bin) + 1)
+                .skip(centre.saturating_sub(PARTIAL_HALF_WIDTH_BINS))
+            {
+                *flag = true;
+            }
+        }
+
+        // Power per bin inside the analysis range, with whether it belongs to a partial
+        let bins: Vec<(f32, f32, bool)> = line_data.iter()
+            .zip(&is_partial)
+            .filter(|(&(freq, _), _)| freq >= low_edge && freq <= high_edge)
+            .map(|(&(freq, db), &partial)| (freq, 10f32.powf(db / 10.0), partial))
+            .collect();
+        // Bins are in dBFS, so a full-scale sine's main lobe sums to about 1
+        let residual_energy: f32 = bins.iter()
+            .filter(|&&(_, _, partial)| !partial)
+            .map(|&(_, power, _)| power)
+            .sum();
+
+        let ratio = (high_edge / low_edge).powf(1.0 / NOISE_BANDS as f32);
+        let mut bands: Vec<(f32, Option<f32>)> = (0..NOISE_BANDS)
+            .map(|band| {
+                let low = low_edge * ratio.powi(band as i32);
+                let high = low * ratio;
+                let (sum, count) = bins.iter()
+                    .filter(|&&(freq, _, partial)| !partial && freq >= low && freq < high)
+                    .fold((0.0f32, 0usize), |(sum, count), &(_, power, _)| (sum + power, count + 1));
+                let db = (count > 0).then(|| 10.0 * (sum / count as f32).max(1e-12).log10());
+                ((low * high).sqrt(), db)
+            })
+            .collect();
+
+        // Bands narrower than a bin, or covered by partials, take the nearest measured band
+        let measured: Vec<(usize, f32)> = bands.iter()
+            .enumerate()
+            .filter_map(|(index, &(_, db))| db.map(|db| (index, db)))
+            .collect();
+        if measured.is_empty() {
+            return Self { bands: Vec::new(), residual_energy };
+        }
+        for (index, band) in bands.iter_mut().enumerate() {
+            if band.1.is_none() {
+                let nearest = measured.iter().min_by_key(|(measured_index, _)| measured_index.abs_diff(index));
+                band.1 = nearest.map(|&(_, db)| db);
+            }
+        }
+
+        Self {
+            bands: bands.into_iter().map(|(freq, db)| (freq, db.unwrap_or_default())).collect(),
+            residual_energy,
+        }
+    }
+
+    /// Envelope level at `freq`, interpolated between band centres on a log-frequency axis.
+    /// `None` outside the measured bands' edges.
+    fn db_at(&self, freq: f32) -> Option<f32> {
+        let (first, last) = (self.bands.first()?, self.bands.last()?);
+        // Half a band beyond the outer centres is still inside the measured range
+        let half_band = if self.bands.len() > 1 { (self.bands[1].0 / self.bands[0].0).sqrt() } else { 1.0 };
+        if freq < first.0 / half_band || freq > last.0 * half_band {
+            return None;
+        }
+        let upper = self.bands.iter().position(|&(centre, _)| centre >= freq);
+        Some(match upper {
+            None => last.1,
+            Some(0) => first.1,
+            Some(upper) => {
+                let (low, high) = (self.bands[upper - 1], self.bands[upper]);
+                let fraction = (freq / low.0).ln() / (high.0 / low.0).ln();
+                low.1 + (high.1 - low.1) * fraction
+            }
+        })
+    }
+}
+
+/// Continuous noise at unit RMS shaped by a residual envelope: short random-phase blocks,
+/// sine-windowed and overlap-added half a block apart so their powers sum to one.
+pub struct NoiseSynth {
+    ifft: Arc<dyn ComplexToReal<f32>>,
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    block: Vec<f32>,
+    window: Vec<f32>,
+    // Finished samples still to hand out, and the second half of the last block
+    ready: Vec<f32>,
+    ready_pos: usize,
+    tail: Vec<f32>,
+    rng: NoiseRng,
+}
+
+impl NoiseSynth {
+    pub fn new(seed: u32) -> Self {
+        let ifft = RealFftPlanner::<f32>::new().plan_fft_inverse(NOISE_BLOCK_LEN);
+        let hop = NOISE_BLOCK_LEN / 2;
+        Self {
+            spectrum: ifft.make_input_vec(),
+            scratch: ifft.make_scratch_vec(),
+            block: ifft.make_output_vec(),
+            window: (0..NOISE_BLOCK_LEN)
+                .map(|n| (std::f32::consts::PI * (n as f32 + 0.5) / NOISE_BLOCK_LEN as f32).sin())
+                .collect(),
+            ifft,
+            ready: vec![0.0; hop],
+            ready_pos: hop,
+            tail: vec![0.0; hop],
+            rng: NoiseRng::new(seed),
+        }
+    }
+
+    /// Fills `out` with the next samples of noise following `envelope`. Band frequencies
+    /// are multiplied by `freq_scale`, like the partials.
+    pub fn fill(&mut self, envelope: &NoiseEnvelope, out: &mut [f32], sample_rate: f32, freq_scale: f32) {
+        for sample in out.iter_mut() {
+            if self.ready_pos == self.ready.len() {
+                self.next_block(envelope, sample_rate, freq_scale);
+            }
+            *sample = self.ready[self.ready_pos];
+            self.ready_pos += 1;
+        }
+    }
+
+    /// Synthesises one windowed block and moves the next hop of overlap-added samples to `ready`.
+    fn next_block(&mut self, envelope: &NoiseEnvelope, sample_rate: f32, freq_scale: f32) {
+        self.block.iter_mut().for_each(|sample| *sample = 0.0);
+        if !envelope.bands.is_empty() && freq_scale > 0.0 {
+            let loudest_db = envelope.bands.iter().fold(f32::NEG_INFINITY, |loudest, &(_, db)| loudest.max(db));
+            let freq_step = sample_rate / NOISE_BLOCK_LEN as f32;
+            for (bin, value) in self.spectrum.iter_mut().enumerate() {
+                let db = envelope.db_at(bin as f32 * freq_step / freq_scale);
+                let magnitude = db.map_or(0.0, |db| 10f32.powf((db - loudest_db) / 20.0));
+                *value = Complex::from_polar(magnitude, self.rng.next_f32() * std::f32::consts::TAU);
+            }
+            // The inverse transform needs real DC and Nyquist bins
+            self.spectrum[0] = Complex::new(0.0, 0.0);
+            if let Some(nyquist) = self.spectrum.last_mut() {
+                nyquist.im = 0.0;
+            }
+            if self.ifft.process_with_scratch(&mut self.spectrum, &mut self.block, &mut self.scratch).is_ok() {
+                let rms = (self.block.iter().map(|s| s * s).sum::<f32>() / NOISE_BLOCK_LEN as f32).sqrt();
+                if rms > 0.0 {
+                    self.block.iter_mut().zip(&self.window).for_each(|(s, w)| *s *= w / rms);
+                }
+            } else {
+                self.block.iter_mut().for_each(|sample| *sample = 0.0);
+            }
+        }
+
+        let hop = self.ready.len();
+        for ((ready, tail), &first) in self.ready.iter_mut().zip(self.tail.iter_mut()).zip(&self.block[..hop]) {
+            *ready = *tail + first;
+        }
+        self.tail.copy_from_slice(&self.block[hop..]);
+        self.ready_pos = 0;
+    }
+}
+
+/// Xorshift generator for noise phases; phases need no better randomness than this.
+pub struct NoiseRng(u32);
+
+impl NoiseRng {
+    pub fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// Uniform in [0, 1).
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
@@ -5,10 +5,20 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use portaudio as pa;
 use log::{info, error, debug, warn};
 use crate::get_results::GuiParameter;
+use crate::fft_analysis::AnalysisData;
+use crate::routing::{OutputRouting, PartialTag, MAX_OUTPUT_CHANNELS};
+use crate::live_bank::{LiveBank, LiveTarget};
+use crate::limiter::{LimiterConfig, OutputMeters, OutputStage};
+use crate::residual::{NoiseEnvelope, NoiseSynth};
+use crate::transforms::{apply_chain, PartialTransform};
+use crate::tuning::Tuning;
+use crate::freeze::FreezeBank;
 use tokio::sync::broadcast;
 
 // Define type alias (same as other files)
 type PartialsData = Vec<Vec<(f32, f32)>>;
+// Analysis labels per partial slot, per channel, alongside `PartialsData`
+type PartialTags = Vec<Vec<PartialTag>>;
 
 // Constants for audio performance - with optimized values for JACK
 #[cfg(target_os = "linux")]
@@ -23,12 +33,43 @@ const MAX_POSSIBLE_GUI_UPDATE_RATE_SECONDS: f32 = 30.0;
 // This will be our fixed actual length for all generated audio segments.
 const FIXED_AUDIO_SEGMENT_LEN_SECONDS: f32 = MAX_POSSIBLE_GUI_UPDATE_RATE_SECONDS;
 const INSTANT_MUTE_FADE_DURATION_SECONDS: f32 = 0.020; // 20ms for a quick mute
+const ONSET_FADE_DURATION_SECONDS: f32 = 0.005; // 5ms so a new note starts close to its onset
+const NOISE_RMS_AT_FULL_LEVEL: f32 = 0.25; // Noise RMS for a residual as loud as a full-scale sine; noise peaks at about 4x RMS
+const LOW_LATENCY_BUFFER_FRAMES: u32 = 256; // About 5ms at 48kHz, so live partials reach the output quickly
+const LATENCY_SMOOTHING: f32 = 0.1; // Weight of each new processing-delay measurement in the reported average
+
+/// Measured delay from a sound reaching the input to its resynthesis leaving the output,
+/// in low-latency mode.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencyReport {
+    pub input_ms: f32,      // Waiting for the input block that carries the sound
+    pub window_ms: f32,     // Half the analysis window
+    pub processing_ms: f32, // Analysis, update and waiting for the next output block, averaged
+    pub output_ms: f32,     // Limiter look-ahead, plus output buffering and device latency as the stream reports them
+}
+
+impl LatencyReport {
+    pub fn total_ms(&self) -> f32 {
+        self.input_ms + self.window_ms + self.processing_ms + self.output_ms
+    }
+}
 
 /// Configuration for resynthesis
 pub struct ResynthConfig {
     pub gain: f32,
     pub freq_scale: f32,  // Frequency scaling factor (1.0 = normal, 2.0 = one octave up, 0.5 = one octave down)
     pub update_rate: f32, // THIS IS THE GUI DRIVEN RATE for refresh/crossfade timing
+    pub onset_boundaries: bool, // Start a new segment at every detected onset
+    pub routing: OutputRouting, // Input channel to output channel gains, and the output channel count
+    pub noise_level: f32,       // Level of the resynthesised residual noise, 0 for partials only
+    pub transpose_semitones: f32, // Pitch shift on top of freq_scale
+    pub transpose_cents: f32,     // Fine pitch shift, added to the semitones
+    pub preserve_formants: bool,  // Keep the spectral envelope in place while the partials move
+    pub transforms: Vec<PartialTransform>, // Applied to the partials in order before synthesis
+    pub low_latency: bool,        // Drive oscillators from each analysis frame through a small output buffer
+    pub latency: Arc<Mutex<Option<LatencyReport>>>, // Measured in low-latency mode, None otherwise
+    pub limiter: LimiterConfig,   // Safety stage at the end of the output path
+    pub meters: Arc<Mutex<OutputMeters>>, // Output levels and gain reduction, written by the audio callback
     pub needs_restart: Arc<AtomicBool>,  // Flag to signal when stream needs to restart
     pub needs_stop: Arc<AtomicBool>,     // Flag to signal when stream needs to stop for buffer resize
     pub output_sample_rate: Arc<Mutex<f64>>, // Store the output sample rate independently
@@ -40,6 +81,17 @@ impl Default for ResynthConfig {
             gain: 0.5,
             freq_scale: 1.0,
             update_rate: DEFAULT_UPDATE_RATE,
+            onset_boundaries: false,
+            routing: OutputRouting::default(),
+            noise_level: 0.0,
+            transpose_semitones: 0.0,
+            transpose_cents: 0.0,
+            preserve_formants: false,
+            transforms: Vec::new(),
+            low_latency: false,
+            latency: Arc::new(Mutex::new(None)),
+            limiter: LimiterConfig::default(),
+            meters: Arc::new(Mutex::new(OutputMeters::default())),
             needs_restart: Arc::new(AtomicBool::new(false)),
             needs_stop: Arc::new(AtomicBool::new(false)),
             output_sample_rate: Arc::new(Mutex::new(0.0)),
@@ -53,6 +105,17 @@ impl Clone for ResynthConfig {
             gain: self.gain,
             freq_scale: self.freq_scale,
             update_rate: self.update_rate,
+            onset_boundaries: self.onset_boundaries,
+            routing: self.routing.clone(),
+            noise_level: self.noise_level,
+            transpose_semitones: self.transpose_semitones,
+            transpose_cents: self.transpose_cents,
+            preserve_formants: self.preserve_formants,
+            transforms: self.transforms.clone(),
+            low_latency: self.low_latency,
+            latency: Arc::clone(&self.latency),
+            limiter: self.limiter,
+            meters: Arc::clone(&self.meters),
             needs_restart: Arc::clone(&self.needs_restart),
             needs_stop: Arc::clone(&self.needs_stop),
             output_sample_rate: Arc::clone(&self.output_sample_rate),
@@ -68,14 +131,83 @@ pub struct SynthUpdate {
     pub freq_scale: f32,
     pub update_rate: f32,  // This is the rate at which this specific set of partials was generated/analyzed.
                            // It IS NOW USED by wavegen_thread to determine generated wave length.
+    pub onset: bool,       // Sent because of an onset: cut the current segment short and fade in quickly
+    pub routing: OutputRouting,
+    pub tags: PartialTags, // Analysis labels per partial, for spreading partials across outputs
+    pub noise: Vec<NoiseEnvelope>,  // Residual envelope per input channel
+    pub noise_level: f32,
+    pub transpose: f32,           // Semitones, cents included
+    pub preserve_formants: bool,
+}
+
+impl SynthUpdate {
+    /// Factor applied to every partial frequency: the frequency scale and the transpose.
+    fn pitch_factor(&self) -> f32 {
+        self.freq_scale * 2f32.powf(self.transpose / 12.0)
+    }
+
+    /// Partials to synthesise. With formants preserved, each keeps its frequency (the pitch
+    /// factor is applied at synthesis) but takes the amplitude its channel's original
+    /// envelope has where it will sound.
+    fn shaped_partials(&self) -> Vec<Vec<(f32, f32)>> {
+        let factor = self.pitch_factor();
+        if !self.preserve_formants || (factor - 1.0).abs() < 1e-6 {
+            return self.partials.clone();
+        }
+        self.partials.iter()
+            .map(|channel| channel.iter()
+                .map(|&(freq, amp)| if freq > 0.0 { (freq, partial_envelope_at(channel, freq * factor)) } else { (freq, amp) })
+                .collect())
+            .collect()
+    }
+
+    /// Partials per output channel as (Hz, amplitude) with routing, shaping and the pitch
+    /// factor applied, for latching into a frozen layer.
+    fn output_partials(&self) -> Vec<Vec<(f32, f32)>> {
+        let factor = self.pitch_factor();
+        self.routing.route_partials(&self.shaped_partials(), &self.tags)
+            .into_iter()
+            .map(|output| output.into_iter().map(|(freq, amp)| (freq * factor, amp)).collect())
+            .collect()
+    }
+
+    /// Routed partials for the live bank, keyed by input, output and track (or slot, for
+    /// untracked partials) so each oscillator carries on from frame to frame.
+    fn live_targets(&self) -> Vec<LiveTarget> {
+        let factor = self.pitch_factor();
+        self.routing.routed(&self.shaped_partials(), &self.tags)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(output, routed)| routed.into_iter().map(move |partial| {
+                let track = self.tags.get(partial.input).and_then(|tags| tags.get(partial.index)).and_then(|tag| tag.track);
+                let id = track.map_or(partial.index as u64, |track| (1 << 32) | track as u64);
+                LiveTarget {
+                    key: ((partial.input as u64) << 48) | ((output as u64) << 40) | id,
+                    output,
+                    freq: partial.freq * factor,
+                    amp: partial.amp,
+                }
+            }))
+            .collect()
+    }
 }
 
-/// Represents a segment of generated stereo audio.
+/// Represents a segment of generated audio, one sample buffer per output channel.
 #[derive(Clone)] // Clone needed for swapping and Option.take()
 struct AudioSegment {
-    left_samples: Vec<f32>,
-    right_samples: Vec<f32>,
+    channels: Vec<Vec<f32>>,
     len_frames: usize, // Length of this specific segment in frames
+    onset: bool,       // Starts at an onset, so crossfade into it quickly
+}
+
+impl AudioSegment {
+    /// Adds frame `index`, scaled by `weight`, into an interleaved output frame. Channels
+    /// the segment doesn't have (after a change of output count) contribute silence.
+    fn add_frame(&self, index: usize, weight: f32, frame: &mut [f32]) {
+        for (out, channel) in frame.iter_mut().zip(&self.channels) {
+            *out += channel[index] * weight;
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -98,20 +230,30 @@ struct WaveSynth {
 
     pub sample_rate: f32,
     current_gain: f32, // GUI gain, applied at playback
+    num_outputs: usize, // Interleaved channels in the output stream's buffers
+    freeze: FreezeBank, // Held layers, sounding on top of the segments
+    live: LiveBank,     // Oscillators played instead of the segments in low-latency mode
+    low_latency: bool,
+    output_latency: f32, // Seconds, as reported by the output stream
+    output_stage: OutputStage, // DC blocker, limiter and soft clip after the gain
 }
 
 impl WaveSynth {
-    fn new(sample_rate: f32) -> Self {
+    fn new(sample_rate: f32, limiter: LimiterConfig, meters: Arc<Mutex<OutputMeters>>) -> Self {
         // Initial silent segment is long to ensure safety during startup.
         let initial_segment_len_frames = (sample_rate * MAX_POSSIBLE_GUI_UPDATE_RATE_SECONDS).max(1.0) as usize;
         let initial_silent_segment = AudioSegment {
-            left_samples: vec![0.0f32; initial_segment_len_frames],
-            right_samples: vec![0.0f32; initial_segment_len_frames],
+            channels: vec![vec![0.0f32; initial_segment_len_frames]; 2],
             len_frames: initial_segment_len_frames,
+            onset: false,
         };
 
         debug!(target: "resynth::synth", "WaveSynth initialized with a silent segment of {} frames.", initial_segment_len_frames);
 
+        let num_outputs = 2;
+        let mut output_stage = OutputStage::new(limiter, sample_rate, meters);
+        output_stage.set_num_outputs(num_outputs);
+
         Self {
             current_segment: Some(initial_silent_segment),
             next_segment: None,
@@ -122,6 +264,12 @@ impl WaveSynth {
             play_state: SynthPlayState::Playing,
             sample_rate,
             current_gain: 0.5, // Default gain
+            num_outputs,
+            freeze: FreezeBank::new(sample_rate),
+            live: LiveBank::default(),
+            low_latency: false,
+            output_latency: 0.0,
+            output_stage,
         }
     }
 
@@ -129,6 +277,18 @@ impl WaveSynth {
         self.current_gain = gain;
     }
 
+    pub fn set_num_outputs(&mut self, num_outputs: usize) {
+        self.num_outputs = num_outputs.max(1);
+        self.output_stage.set_num_outputs(self.num_outputs);
+    }
+
+    /// Switches between segment playback and the live bank, starting the bank from silence.
+    pub fn set_low_latency(&mut self, enabled: bool, output_latency: f32) {
+        self.low_latency = enabled;
+        self.output_latency = output_latency;
+        self.live = LiveBank::default();
+    }
+
     /// Called by the outer timed loop in start_resynth_thread to initiate a switch.
     pub fn prepare_for_crossfade(&mut self, new_segment: AudioSegment, gui_update_rate_for_fade: f32, new_segment_target_gain: f32) {
         // current_segment is guaranteed to be Some due to initialization in new().
@@ -146,9 +306,13 @@ impl WaveSynth {
         self.next_segment = Some(new_segment);
         self.next_cursor_frames = 0; // New segment starts from its beginning for the fade-in
 
+        let starts_at_onset = self.next_segment.as_ref().is_some_and(|s| s.onset);
         let fade_duration_seconds = if new_segment_target_gain < 0.001 { // If gain is effectively zero
             debug!(target: "audio_streaming::resynth", "Target gain is near zero ({:.3}). Overriding fade to be very short ({:.3}s) for mute effect.", new_segment_target_gain, INSTANT_MUTE_FADE_DURATION_SECONDS);
             INSTANT_MUTE_FADE_DURATION_SECONDS
+        } else if starts_at_onset {
+            debug!(target: "audio_streaming::resynth", "Segment starts at an onset. Using a short {:.3}s fade.", ONSET_FADE_DURATION_SECONDS);
+            ONSET_FADE_DURATION_SECONDS
         } else {
             gui_update_rate_for_fade / 3.0 // Standard fade: 1/3 of the current GUI update rate
         };
@@ -159,24 +323,27 @@ impl WaveSynth {
         debug!(target: "audio_streaming::resynth", "Crossfade prepared: {} total fade frames (derived from {:.3}s duration).", self.total_fade_duration_frames, fade_duration_seconds);
     }
 
-    /// Fills the output buffer with audio samples. Called by PortAudio callback.
+    /// Fills the interleaved output buffer with audio samples. Called by PortAudio callback.
     pub fn process_buffer(&mut self, out_buffer: &mut [f32]) {
         debug!(target: "audio_streaming::resynth", "PROCESS_BUFFER_ENTRY: WaveSynth::process_buffer entered. Play_state: {:?}", self.play_state);
 
-        let frames_to_fill = out_buffer.len() / 2; // Assuming stereo
-
-        for i in 0..frames_to_fill {
-            let mut sample_l = 0.0;
-            let mut sample_r = 0.0;
+        let num_outputs = self.num_outputs;
+        if self.low_latency {
+            self.live.begin_block(out_buffer.len() / num_outputs, self.sample_rate);
+        }
+        for frame in out_buffer.chunks_exact_mut(num_outputs) {
+            frame.fill(0.0);
 
             match self.play_state {
+                _ if self.low_latency => {
+                    self.live.add_frame(frame);
+                }
                 SynthPlayState::Playing => {
                     if let Some(curr) = &self.current_segment {
                         if self.current_cursor_frames < curr.len_frames {
-                            sample_l = curr.left_samples[self.current_cursor_frames];
-                            sample_r = curr.right_samples[self.current_cursor_frames];
+                            curr.add_frame(self.current_cursor_frames, 1.0, frame);
                             if self.current_cursor_frames < 5 {
-                                debug!(target: "audio_streaming::resynth", "Playing frame {}: L={:.4}, R={:.4} from current_segment (len {})", self.current_cursor_frames, sample_l, sample_r, curr.len_frames);
+                                debug!(target: "audio_streaming::resynth", "Playing frame {}: {:?} from current_segment (len {})", self.current_cursor_frames, frame, curr.len_frames);
                             }
                             self.current_cursor_frames += 1;
                         } else {
@@ -198,14 +365,10 @@ impl WaveSynth {
                     let fade_out_factor = 1.0 - fade_ratio;
                     let fade_in_factor = fade_ratio;
 
-                    let mut s_l_curr = 0.0; let mut s_r_curr = 0.0;
-                    let mut s_l_next = 0.0; let mut s_r_next = 0.0;
-
                     // current_segment is the outgoing segment
                     if let Some(curr) = &self.current_segment {
                         if self.current_cursor_frames < curr.len_frames {
-                            s_l_curr = curr.left_samples[self.current_cursor_frames];
-                            s_r_curr = curr.right_samples[self.current_cursor_frames];
+                            curr.add_frame(self.current_cursor_frames, fade_out_factor, frame);
                         } else {
                             debug!(target: "audio_streaming::resynth", "Crossfade: current_segment underrun at frame {} (len {}). Outputting 0.", self.current_cursor_frames, curr.len_frames);
                         }
@@ -213,16 +376,12 @@ impl WaveSynth {
                     // next_segment is the incoming segment
                     if let Some(nxt) = &self.next_segment {
                         if self.next_cursor_frames < nxt.len_frames {
-                            s_l_next = nxt.left_samples[self.next_cursor_frames];
-                            s_r_next = nxt.right_samples[self.next_cursor_frames];
+                            nxt.add_frame(self.next_cursor_frames, fade_in_factor, frame);
                         } else {
                             debug!(target: "audio_streaming::resynth", "Crossfade: next_segment underrun at frame {} (len {}). Outputting 0.", self.next_cursor_frames, nxt.len_frames);
                         }
                     }
 
-                    sample_l = s_l_curr * fade_out_factor + s_l_next * fade_in_factor;
-                    sample_r = s_r_curr * fade_out_factor + s_r_next * fade_in_factor;
-
                     // Log fade factors and cursor positions for first and last 5 frames of crossfade
                     if self.fade_progress_frames < 5 || self.fade_progress_frames + 5 >= self.total_fade_duration_frames {
                         debug!(target: "audio_streaming::resynth", 
@@ -251,60 +410,23 @@ impl WaveSynth {
                     }
                 }
             }
-            // Apply gain at playback
-            out_buffer[i * 2] = sample_l * self.current_gain;
-            out_buffer[i * 2 + 1] = sample_r * self.current_gain;
-        }
-    }
-
-    // Helper to combine partials for stereo, can be used by wavegen_thread
-    // This function was part of the old WaveSynth, kept for utility.
-    pub fn combine_partials_to_stereo(partials_data: &[Vec<(f32, f32)>]) -> [Vec<(f32, f32)>; 2] {
-        let mut left_ch_partials = Vec::new();
-        let mut right_ch_partials = Vec::new();
-        let mut left_sources_count = 0;
-        let mut right_sources_count = 0;
-
-        for (idx, channel_partials_vec) in partials_data.iter().enumerate() {
-            if idx % 2 == 0 { // Even index for left channel
-                left_ch_partials.extend_from_slice(channel_partials_vec);
-                left_sources_count += 1;
-            } else { // Odd index for right channel
-                right_ch_partials.extend_from_slice(channel_partials_vec);
-                right_sources_count += 1;
+            // Make way for held layers, then add them
+            let live_weight = self.freeze.next_live_weight();
+            if live_weight < 1.0 {
+                frame.iter_mut().for_each(|sample| *sample *= live_weight);
             }
-        }
+            self.freeze.add_frame(frame);
 
-        // Attenuate if multiple sources contributed to L or R
-        if left_sources_count > 1 {
-            for p in left_ch_partials.iter_mut() {
-                p.1 /= left_sources_count as f32;
-            }
-        }
-        if right_sources_count > 1 {
-            for p in right_ch_partials.iter_mut() {
-                p.1 /= right_sources_count as f32;
-            }
-        }
-
-        // --- Scaling to prevent sum of amplitudes > 1.0 ---
-        let left_sum: f32 = left_ch_partials.iter().map(|p| p.1.abs()).sum();
-        if left_sum > 1.0 {
-            let scale = 1.0 / left_sum;
-            for p in left_ch_partials.iter_mut() {
-                p.1 *= scale;
+            // Apply gain at playback
+            for sample in frame.iter_mut() {
+                *sample *= self.current_gain;
             }
-            debug!(target: "audio_streaming::resynth", "Scaling left partials by {:.4} to prevent clipping (sum was {:.4})", scale, left_sum);
         }
-        let right_sum: f32 = right_ch_partials.iter().map(|p| p.1.abs()).sum();
-        if right_sum > 1.0 {
-            let scale = 1.0 / right_sum;
-            for p in right_ch_partials.iter_mut() {
-                p.1 *= scale;
-            }
-            debug!(target: "audio_streaming::resynth", "Scaling right partials by {:.4} to prevent clipping (sum was {:.4})", scale, right_sum);
+        self.freeze.end_buffer();
+        if self.low_latency {
+            self.live.end_block();
         }
-        [left_ch_partials, right_ch_partials]
+        self.output_stage.process(out_buffer, num_outputs);
     }
 }
 
@@ -324,6 +446,12 @@ fn start_wavegen_thread(
     debug!(target: "resynth::wavegen", "Initial segment length: {} frames", fixed_segment_len_frames);
 
     thread::spawn(move || {
+        // An onset update that arrived mid-segment and starts the next segment
+        let mut carried_update: Option<SynthUpdate> = None;
+        let seed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(1, |d| d.subsec_nanos());
+        // One noise generator per input channel, so each channel's noise runs on across segments
+        let mut noise_synths: Vec<NoiseSynth> = Vec::new();
+
         while !shutdown_flag.load(Ordering::Relaxed) {
             // Check for sample rate updates first
             if let Ok(new_sample_rate) = sample_rate_rx.try_recv() {
@@ -339,22 +467,24 @@ fn start_wavegen_thread(
             }
             
             // Block for the definitive next update to process for a new segment
-            let mut current_update = match update_rx.recv() { 
-                Ok(upd) => upd,
-                Err(_) => { // Changed from mpsc::RecvError for clarity with blocking recv
-                    error!(target: "resynth::wavegen", "Update channel disconnected. Shutting down wavegen thread.");
-                    break;
-                }
+            let mut current_update = match carried_update.take() {
+                Some(upd) => upd,
+                None => match update_rx.recv() { 
+                    Ok(upd) => upd,
+                    Err(_) => { // Changed from mpsc::RecvError for clarity with blocking recv
+                        error!(target: "resynth::wavegen", "Update channel disconnected. Shutting down wavegen thread.");
+                        break;
+                    }
+                },
             };
 
             debug!(target: "audio_streaming::resynth::wavegen", 
                    "Starting new segment synthesis with initial Gain: {:.2}, FScale: {:.2}, Partials: {} chans", 
                    current_update.gain, current_update.freq_scale, current_update.partials.len());
 
-            let mut left_samples = vec![0.0f32; fixed_segment_len_frames];
-            let mut right_samples = vec![0.0f32; fixed_segment_len_frames];
-            // Initial combination of partials based on the starting update
-            let mut stereo_partials_arrays = WaveSynth::combine_partials_to_stereo(&current_update.partials);
+            // Initial routing of partials to the output channels based on the starting update
+            let mut output_partials = current_update.routing.route_partials(&current_update.shaped_partials(), &current_update.tags);
+            let mut channel_samples = vec![vec![0.0f32; fixed_segment_len_frames]; output_partials.len()];
 
             const SUB_CHUNK_FRAMES: usize = 4096; // Approx 85ms at 48kHz. Tune as needed.
             let wavegen_segment_start_time = Instant::now();
@@ -365,10 +495,19 @@ fn start_wavegen_thread(
 
                 // Before synthesizing this sub-chunk, check for newer updates from get_results
                 match update_rx.try_recv() {
+                    Ok(newly_arrived_update) if newly_arrived_update.onset => {
+                        // An onset is a hard boundary: drop this segment and start the next one from the onset
+                        debug!(target: "resynth::wavegen", "Onset update arrived mid-segment. Abandoning the current segment.");
+                        carried_update = Some(newly_arrived_update);
+                        break;
+                    }
                     Ok(newly_arrived_update) => {
                         // Compare critical parameters to see if a meaningful change occurred
                         if newly_arrived_update.gain != current_update.gain || 
                            newly_arrived_update.freq_scale != current_update.freq_scale || 
+                           newly_arrived_update.transpose != current_update.transpose ||
+                           newly_arrived_update.preserve_formants != current_update.preserve_formants ||
+                           newly_arrived_update.routing != current_update.routing ||
                            newly_arrived_update.partials.len() != current_update.partials.len() || // Basic check for partials change
                            !newly_arrived_update.partials.iter().zip(current_update.partials.iter()).all(|(v1,v2)| v1.len() == v2.len()) // Deeper check if needed
                         {
@@ -377,7 +516,9 @@ fn start_wavegen_thread(
                                    current_update.gain, newly_arrived_update.gain, current_update.freq_scale, newly_arrived_update.freq_scale);
                             current_update = newly_arrived_update; // Adopt new parameters
                             // Re-process partials if they have changed structure or content significantly
-                            stereo_partials_arrays = WaveSynth::combine_partials_to_stereo(&current_update.partials); 
+                            output_partials = current_update.routing.route_partials(&current_update.shaped_partials(), &current_update.tags);
+                            // A new output count applies from the next segment
+                            output_partials.resize(channel_samples.len(), Vec::new());
                         }
                     }
                     Err(mpsc::TryRecvError::Empty) => { /* No new update, continue with current_update */ }
@@ -390,20 +531,19 @@ fn start_wavegen_thread(
                 if shutdown_flag.load(Ordering::Relaxed) { break; } // Check again after try_recv
 
                 // Synthesize one sub-chunk using current_update parameters
+                let pitch_factor = current_update.pitch_factor();
                 for frame_idx_offset in 0..SUB_CHUNK_FRAMES {
                     let frame_idx = frame_chunk_start + frame_idx_offset;
                     if frame_idx >= fixed_segment_len_frames { break; }
 
                     let time = frame_idx as f32 / current_sample_rate;
                     
-                    for ch_idx in 0..2 { // 0 for Left, 1 for Right
-                        let target_buffer = if ch_idx == 0 { &mut left_samples } else { &mut right_samples };
-                        let source_partials = &stereo_partials_arrays[ch_idx];
+                    for (target_buffer, source_partials) in channel_samples.iter_mut().zip(&output_partials) {
                         let mut sample_val = 0.0f32;
 
                         for &(freq, amp) in source_partials.iter() {
                             if freq > 0.0 && amp > 0.0 { // Ensure partials are valid
-                                let phase = 2.0 * std::f32::consts::PI * (freq * current_update.freq_scale) * time;
+                                let phase = 2.0 * std::f32::consts::PI * (freq * pitch_factor) * time;
                                 sample_val += amp * phase.sin();
                             }
                         }
@@ -416,6 +556,33 @@ fn start_wavegen_thread(
             } // End of sub-chunk synthesis loop
 
             if shutdown_flag.load(Ordering::Relaxed) { break; } // Check after main synthesis loop for the segment
+            if carried_update.is_some() { continue; } // Superseded by an onset before it was finished
+
+            // Add each input channel's residual as shaped noise, routed like its partials
+            if current_update.noise_level > 0.0 {
+                let gains = current_update.routing.effective_gains(current_update.noise.len());
+                // Noise is all envelope, so preserving formants leaves it unshifted
+                let noise_scale = if current_update.preserve_formants { 1.0 } else { current_update.pitch_factor() };
+                let mut noise = vec![0.0f32; fixed_segment_len_frames];
+                for (channel, (envelope, row)) in current_update.noise.iter().zip(&gains).enumerate() {
+                    // Follows the residual's own level, so a quiet input gives quiet noise
+                    let level = NOISE_RMS_AT_FULL_LEVEL * current_update.noise_level * envelope.residual_energy.sqrt();
+                    if level <= 0.0 {
+                        continue;
+                    }
+                    while noise_synths.len() <= channel {
+                        noise_synths.push(NoiseSynth::new(seed.wrapping_add(noise_synths.len() as u32)));
+                    }
+                    noise_synths[channel].fill(envelope, &mut noise, current_sample_rate, noise_scale);
+                    for (target_buffer, &gain) in channel_samples.iter_mut().zip(row) {
+                        if gain > 0.0 {
+                            for (sample, noise_sample) in target_buffer.iter_mut().zip(&noise) {
+                                *sample += noise_sample * level * gain;
+                            }
+                        }
+                    }
+                }
+            }
             
             let segment_synthesis_duration = wavegen_segment_start_time.elapsed();
             // Log the gain that was active at the *end* of synthesis for this segment.
@@ -427,24 +594,22 @@ fn start_wavegen_thread(
             let final_gain_for_segment_log = current_update.gain;
             if fixed_segment_len_frames > 0 {
                 let num_samples_to_log = 5.min(fixed_segment_len_frames);
-                let l_samples_head_str: Vec<String> = left_samples.iter().take(num_samples_to_log).map(|s| format!("{:.4}", s)).collect();
-                let r_samples_head_str: Vec<String> = right_samples.iter().take(num_samples_to_log).map(|s| format!("{:.4}", s)).collect();
-                
-                let max_abs_left_post_gain = left_samples.iter().fold(0.0f32, |max, &val| max.max(val.abs()));
-                let max_abs_right_post_gain = right_samples.iter().fold(0.0f32, |max, &val| max.max(val.abs()));
-
-                debug!(target: "audio_streaming::resynth::wavegen_detail", 
-                       "Generated segment samples (final gain for log: {:.4}). Left[0..{}]: [{}]. Right[0..{}]: [{}]. Max L Post-Gain: {:.4}, Max R Post-Gain: {:.4}", 
-                       final_gain_for_segment_log, 
-                       num_samples_to_log, l_samples_head_str.join(", "), 
-                       num_samples_to_log, r_samples_head_str.join(", "),
-                       max_abs_left_post_gain, max_abs_right_post_gain);
+                for (ch_idx, samples) in channel_samples.iter().enumerate() {
+                    let samples_head_str: Vec<String> = samples.iter().take(num_samples_to_log).map(|s| format!("{:.4}", s)).collect();
+                    let max_abs_post_gain = samples.iter().fold(0.0f32, |max, &val| max.max(val.abs()));
+
+                    debug!(target: "audio_streaming::resynth::wavegen_detail", 
+                           "Generated segment samples (final gain for log: {:.4}). Out {}[0..{}]: [{}]. Max Post-Gain: {:.4}", 
+                           final_gain_for_segment_log, 
+                           ch_idx + 1, num_samples_to_log, samples_head_str.join(", "),
+                           max_abs_post_gain);
+                }
             }
 
             let new_segment = AudioSegment {
-                left_samples,
-                right_samples,
+                channels: channel_samples,
                 len_frames: fixed_segment_len_frames, 
+                onset: current_update.onset,
             };
             
             let mut slot_guard = incoming_segment_slot.lock().unwrap();
@@ -455,18 +620,62 @@ fn start_wavegen_thread(
     });
 }
 
-/// Filter partials to only include frequencies within the output device's supported range
-fn filter_partials_for_output(partials: &[Vec<(f32, f32)>], output_sample_rate: f32) -> Vec<Vec<(f32, f32)>> {
+/// Filter partials to only include frequencies within the output device's supported range,
+/// keeping each partial's tag alongside it, and turn their dBFS levels into the linear
+/// amplitudes the oscillators play (0 for empty slots)
+fn filter_partials_for_output(
+    partials: &[Vec<(f32, f32)>],
+    tags: &[Vec<PartialTag>],
+    output_sample_rate: f32,
+) -> (PartialsData, PartialTags) {
     // Calculate Nyquist frequency for the output device (half the sample rate)
     let output_nyquist = output_sample_rate / 2.0;
     
     // Filter partials for each channel
-    partials.iter().map(|channel_partials| {
+    partials.iter().enumerate().map(|(channel, channel_partials)| {
         channel_partials.iter()
-            .filter(|(freq, _)| *freq <= output_nyquist)
-            .copied()
-            .collect()
-    }).collect()
+            .enumerate()
+            .filter(|(_, (freq, _))| *freq <= output_nyquist)
+            .map(|(index, &(freq, db))| {
+                let tag = tags.get(channel).and_then(|channel_tags| channel_tags.get(index)).copied().unwrap_or_default();
+                let amplitude = if freq > 0.0 { 10.0_f32.powf(db / 20.0) } else { 0.0 };
+                ((freq, amplitude), tag)
+            })
+            .unzip()
+    }).unzip()
+}
+
+/// Amplitude of a channel's partials at `freq`, interpolated on a log-frequency axis between
+/// neighbouring partials and held at the outermost partial beyond them.
+fn partial_envelope_at(partials: &[(f32, f32)], freq: f32) -> f32 {
+    let mut points: Vec<(f32, f32)> = partials.iter().copied().filter(|&(f, _)| f > 0.0).collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else { return 0.0 };
+    if freq <= first.0 {
+        return first.1;
+    }
+    if freq >= last.0 {
+        return last.1;
+    }
+    let upper = points.iter().position(|&(f, _)| f >= freq).unwrap_or(points.len() - 1);
+    let (low, high) = (points[upper - 1], points[upper]);
+    let fraction = (freq / low.0).ln() / (high.0 / low.0).ln();
+    low.1 + (high.1 - low.1) * fraction
+}
+
+/// Harmonic number and track ID of every partial slot, from the analysis of the same frame.
+fn partial_tags(analysis: Option<&AnalysisData>) -> PartialTags {
+    analysis.map_or_else(Vec::new, |analysis| analysis.iter()
+        .map(|channel| {
+            let slots = channel.tracks.len().max(channel.harmonics.partials.len());
+            (0..slots)
+                .map(|index| PartialTag {
+                    harmonic: channel.harmonics.partials.get(index).copied().flatten().map(|label| label.harmonic),
+                    track: channel.tracks.get(index).copied().flatten(),
+                })
+                .collect()
+        })
+        .collect())
 }
 
 /// Starts a thread that performs real-time resynthesis of the analyzed spectrum.
@@ -480,6 +689,7 @@ pub fn start_resynth_thread(
     num_partials: usize,
     gui_param_rx: mpsc::Receiver<GuiParameter>,
     gain_update_rx: mpsc::Receiver<f32>,
+    analysis_rx: broadcast::Receiver<AnalysisData>,
 ) {
     debug!("Resynth thread starting - {} channels, {} partials per channel", num_channels, num_partials);
 
@@ -502,7 +712,10 @@ pub fn start_resynth_thread(
     let (update_tx, update_rx) = mpsc::channel::<SynthUpdate>();
     
     // Create the WaveSynth instance for the audio callback
-    let synth_instance = Arc::new(Mutex::new(WaveSynth::new(sample_rate as f32)));
+    let (limiter, meters) = config.lock()
+        .map(|config| (config.limiter, Arc::clone(&config.meters)))
+        .unwrap_or_else(|_| (LimiterConfig::default(), Arc::new(Mutex::new(OutputMeters::default()))));
+    let synth_instance = Arc::new(Mutex::new(WaveSynth::new(sample_rate as f32, limiter, meters)));
     let synth_instance_clone = Arc::clone(&synth_instance);
 
     // Clone config for the update thread
@@ -519,7 +732,13 @@ pub fn start_resynth_thread(
     let _update_thread = thread::spawn(move || {
         // This thread subscribes to the broadcast channel and forwards updates to the wavegen thread
         let mut partials_rx = partials_rx;
+        let mut analysis_rx = analysis_rx;
         let mut last_update = Instant::now();
+        let mut pending_onset = false;
+        let mut latest_analysis: Option<AnalysisData> = None;  // Labels the partials for spreading
+        let mut tuning = Tuning::default();  // Scale the Quantize transform snaps to
+        let mut latest_update: Option<SynthUpdate> = None;  // What a freeze latches
+        let mut channel_mask: Vec<bool> = Vec::new();  // Empty until the GUI sends one: every channel plays
         let mut config_clone = ResynthConfig::default();
 
         // Get initial config values
@@ -528,6 +747,17 @@ pub fn start_resynth_thread(
                 gain: cfg.gain,
                 freq_scale: cfg.freq_scale,
                 update_rate: cfg.update_rate,
+                onset_boundaries: cfg.onset_boundaries,
+                routing: cfg.routing.clone(),
+                noise_level: cfg.noise_level,
+                transpose_semitones: cfg.transpose_semitones,
+                transpose_cents: cfg.transpose_cents,
+                preserve_formants: cfg.preserve_formants,
+                transforms: cfg.transforms.clone(),
+                low_latency: cfg.low_latency,
+                latency: Arc::clone(&cfg.latency),
+                limiter: cfg.limiter,
+                meters: Arc::clone(&cfg.meters),
                 needs_restart: Arc::clone(&cfg.needs_restart),
                 needs_stop: Arc::clone(&cfg.needs_stop),
                 output_sample_rate: Arc::clone(&cfg.output_sample_rate),
@@ -550,6 +780,104 @@ pub fn start_resynth_thread(
                         debug!(target: "resynth::update", "Received Gain: {}", gain);
                         config_clone.gain = gain;
                     },
+                    GuiParameter::ChannelMask(mask) => {
+                        debug!(target: "resynth::update", "Received ChannelMask: {:?}", mask);
+                        channel_mask = mask;
+                    },
+                    GuiParameter::Routing(routing) => {
+                        debug!(target: "resynth::update", "Received Routing: {} outputs", routing.num_outputs);
+                        config_clone.routing = routing;
+                    },
+                    GuiParameter::NoiseLevel(level) => {
+                        debug!(target: "resynth::update", "Received NoiseLevel: {}", level);
+                        config_clone.noise_level = level;
+                    },
+                    GuiParameter::TransposeSemitones(semitones) => {
+                        debug!(target: "resynth::update", "Received TransposeSemitones: {}", semitones);
+                        config_clone.transpose_semitones = semitones;
+                    },
+                    GuiParameter::TransposeCents(cents) => {
+                        debug!(target: "resynth::update", "Received TransposeCents: {}", cents);
+                        config_clone.transpose_cents = cents;
+                    },
+                    GuiParameter::PreserveFormants(enabled) => {
+                        debug!(target: "resynth::update", "Received PreserveFormants: {}", enabled);
+                        config_clone.preserve_formants = enabled;
+                    },
+                    GuiParameter::Transforms(transforms) => {
+                        debug!(target: "resynth::update", "Received Transforms: {:?}", transforms);
+                        config_clone.transforms = transforms;
+                    },
+                    GuiParameter::Tuning(new_tuning) => {
+                        debug!(target: "resynth::update", "Received Tuning: {:?}", new_tuning.config);
+                        tuning = new_tuning;
+                    },
+                    GuiParameter::FreezeLayer(id) => {
+                        debug!(target: "resynth::update", "Received FreezeLayer: {}", id);
+                        let outputs = latest_update.as_ref().map_or_else(Vec::new, SynthUpdate::output_partials);
+                        if outputs.is_empty() {
+                            warn!(target: "resynth::update", "Freeze requested before any partials arrived; layer {} is silent", id);
+                        }
+                        if let Ok(mut synth) = synth_instance_clone.lock() {
+                            let synth_sample_rate = synth.sample_rate;
+                            synth.freeze.freeze(id, &outputs, synth_sample_rate);
+                        }
+                    },
+                    GuiParameter::ReleaseLayer(id) => {
+                        debug!(target: "resynth::update", "Received ReleaseLayer: {}", id);
+                        if let Ok(mut synth) = synth_instance_clone.lock() {
+                            synth.freeze.release(id);
+                        }
+                    },
+                    GuiParameter::LayerLevel(id, level) => {
+                        if let Ok(mut synth) = synth_instance_clone.lock() {
+                            synth.freeze.set_level(id, level);
+                        }
+                    },
+                    GuiParameter::Limiter(limiter) => {
+                        debug!(target: "resynth::update", "Received Limiter: {:?}", limiter);
+                        config_clone.limiter = limiter;
+                        if let Ok(mut synth) = synth_instance_clone.lock() {
+                            let synth_sample_rate = synth.sample_rate;
+                            synth.output_stage.set_config(limiter, synth_sample_rate);
+                        }
+                    },
+                    GuiParameter::LowLatency(enabled) => {
+                        debug!(target: "resynth::update", "Received LowLatency: {}", enabled);
+                        config_clone.low_latency = enabled;
+                        if !enabled {
+                            if let Ok(mut report) = config_clone.latency.lock() {
+                                *report = None;
+                            }
+                        }
+                    },
+                    GuiParameter::MuteLiveWhileFrozen(enabled) => {
+                        debug!(target: "resynth::update", "Received MuteLiveWhileFrozen: {}", enabled);
+                        if let Ok(mut synth) = synth_instance_clone.lock() {
+                            synth.freeze.set_mute_live(enabled);
+                        }
+                    },
+                }
+            }
+
+            // Onset boundaries are set on the shared config; keep the last value while it's busy
+            if let Ok(cfg) = config_for_update.try_lock() {
+                config_clone.onset_boundaries = cfg.onset_boundaries;
+            }
+
+            // Check for onsets; the analysis for a frame is published before its partials
+            loop {
+                match analysis_rx.try_recv() {
+                    Ok(analysis) => {
+                        if config_clone.onset_boundaries && analysis.iter().any(|channel| channel.onset.is_some()) {
+                            pending_onset = true;
+                        }
+                        latest_analysis = Some(analysis);
+                    },
+                    Err(broadcast::error::TryRecvError::Lagged(n)) => {
+                        warn!(target: "resynth::update", "Resynth analysis receiver lagged by {} messages", n);
+                    },
+                    Err(_) => break,
                 }
             }
 
@@ -564,35 +892,96 @@ pub fn start_resynth_thread(
             // Check for new partials data
             match partials_rx.try_recv() {
                 Ok(partials) => {
-                    if last_update.elapsed() >= Duration::from_secs_f32(config_clone.update_rate) {
-                        // Filter partials to only include frequencies within the output device's supported range
-                        let filtered_partials = filter_partials_for_output(&partials, sample_rate as f32);
-                        
-                        // Log how many partials were filtered out
-                        let original_count: usize = partials.iter().map(|channel| channel.len()).sum();
-                        let filtered_count: usize = filtered_partials.iter().map(|channel| channel.len()).sum();
-                        if filtered_count < original_count {
-                            debug!(target: "resynth::update", "Filtered partials for output: {} -> {} (removed {} that exceed output Nyquist frequency of {} Hz)",
-                                original_count, filtered_count, original_count - filtered_count, sample_rate as f32 / 2.0);
+                    // Filter partials to only include frequencies within the output device's supported range
+                    let tags = partial_tags(latest_analysis.as_ref());
+                    let (mut filtered_partials, filtered_tags) = filter_partials_for_output(&partials, &tags, sample_rate as f32);
+
+                    // Creative transforms, each channel about its own fundamental
+                    let f0s: Vec<Option<f32>> = latest_analysis.as_ref()
+                        .map_or_else(Vec::new, |analysis| analysis.iter().map(|channel| channel.pitch.map(|p| p.f0)).collect());
+                    apply_chain(&config_clone.transforms, &mut filtered_partials, &f0s, &tuning, sample_rate as f32 / 2.0);
+
+                    let mut noise: Vec<NoiseEnvelope> = latest_analysis.as_ref()
+                        .map_or_else(Vec::new, |analysis| analysis.iter().map(|channel| channel.noise.clone()).collect());
+
+                    // Silence muted channels but keep their slots so the channel layout is unchanged
+                    for (channel, channel_partials) in filtered_partials.iter_mut().enumerate() {
+                        if !channel_mask.get(channel).copied().unwrap_or(true) {
+                            channel_partials.clear();
+                            if let Some(envelope) = noise.get_mut(channel) {
+                                *envelope = NoiseEnvelope::default();
+                            }
                         }
-                        
-                        // Create update with filtered partials
-                        let update = SynthUpdate {
-                            partials: filtered_partials,
-                            gain: config_clone.gain,
-                            freq_scale: config_clone.freq_scale,
-                            update_rate: config_clone.update_rate,
+                    }
+                    
+                    // Log how many partials were filtered out
+                    let original_count: usize = partials.iter().map(|channel| channel.len()).sum();
+                    let filtered_count: usize = filtered_partials.iter().map(|channel| channel.len()).sum();
+                    if filtered_count < original_count {
+                        debug!(target: "resynth::update", "Filtered partials for output: {} -> {} (removed {} that exceed output Nyquist frequency of {} Hz)",
+                            original_count, filtered_count, original_count - filtered_count, sample_rate as f32 / 2.0);
+                    }
+                    
+                    // Create update with filtered partials
+                    let update = SynthUpdate {
+                        partials: filtered_partials,
+                        gain: config_clone.gain,
+                        freq_scale: config_clone.freq_scale,
+                        update_rate: config_clone.update_rate,
+                        onset: pending_onset,
+                        routing: config_clone.routing.clone(),
+                        tags: filtered_tags,
+                        noise,
+                        noise_level: config_clone.noise_level,
+                        transpose: config_clone.transpose_semitones + config_clone.transpose_cents / 100.0,
+                        preserve_formants: config_clone.preserve_formants,
+                    };
+
+                    // Low latency hands every frame to the live bank for the next audio block
+                    if config_clone.low_latency {
+                        pending_onset = false;
+                        let timing = latest_analysis.as_ref().and_then(|analysis| analysis.iter().find_map(|channel| channel.timing));
+                        let captured_at = timing.map_or_else(Instant::now, |timing| timing.captured_at);
+                        let targets = update.live_targets();
+                        let (adopted_delay, output_latency) = match synth_instance_clone.lock() {
+                            Ok(mut synth) => {
+                                synth.live.set_targets(&targets, captured_at);
+                                (synth.live.take_adopted_delay(), synth.output_latency + synth.output_stage.latency_seconds())
+                            }
+                            Err(_) => (None, 0.0),
                         };
-                        
-                        if let Err(e) = update_tx.send(update) {
+                        if let (Some(delay), Some(timing)) = (adopted_delay, timing) {
+                            if let Ok(mut report) = config_clone.latency.lock() {
+                                let processing_ms = delay * 1000.0;
+                                let processing_ms = report.map_or(processing_ms, |previous| {
+                                    previous.processing_ms + (processing_ms - previous.processing_ms) * LATENCY_SMOOTHING
+                                });
+                                *report = Some(LatencyReport {
+                                    input_ms: timing.input_block_seconds * 1000.0,
+                                    window_ms: timing.window_seconds * 1000.0,
+                                    processing_ms,
+                                    output_ms: output_latency * 1000.0,
+                                });
+                            }
+                        }
+                    } else if pending_onset || last_update.elapsed() >= Duration::from_secs_f32(config_clone.update_rate) {
+                        // An onset sends its frame's partials straight away, whatever the update timer says
+                        if pending_onset {
+                            debug!(target: "resynth::update", "Sending onset update");
+                        }
+                        pending_onset = false;
+
+                        if let Err(e) = update_tx.send(update.clone()) {
                             error!(target: "resynth::update", "Failed to send update to wavegen thread: {}", e);
                         }
                         last_update = Instant::now();
                     }
+                    // Every frame is prepared, so a freeze latches the latest analysis frame
+                    latest_update = Some(update);
                 },
                 Err(broadcast::error::TryRecvError::Empty) => {
-                    // No new data, wait a bit
-                    thread::sleep(Duration::from_millis(10));
+                    // No new data, wait a bit; less in low latency, where the wait adds to the delay
+                    thread::sleep(Duration::from_millis(if config_clone.low_latency { 1 } else { 10 }));
                 },
                 Err(broadcast::error::TryRecvError::Lagged(n)) => {
                     warn!(target: "resynth::update", "Resynth thread lagged by {} messages", n);
@@ -615,7 +1004,15 @@ pub fn start_resynth_thread(
         debug!(target: "resynth::main", "Starting resynth main thread");
         
         // Setup audio output stream
-        let mut stream_result = setup_audio_stream(device_index, sample_rate, Arc::clone(&pa_synth_instance_accessor));
+        let num_outputs = |accessor: &Arc<Mutex<ResynthConfig>>| accessor.lock().map_or(2, |config| config.routing.num_outputs);
+        let low_latency = |accessor: &Arc<Mutex<ResynthConfig>>| accessor.lock().is_ok_and(|config| config.low_latency);
+        let mut stream_result = setup_audio_stream(
+            device_index,
+            sample_rate,
+            num_outputs(&resynth_config_accessor),
+            low_latency(&resynth_config_accessor),
+            Arc::clone(&pa_synth_instance_accessor),
+        );
         let mut stream = match stream_result {
             Ok(s) => Some(s),
             Err(e) => {
@@ -667,7 +1064,7 @@ pub fn start_resynth_thread(
                         config.needs_restart.store(false, Ordering::SeqCst);
                         
                         // Try to reinitialize the stream
-                        match setup_audio_stream(device_index, sample_rate, Arc::clone(&pa_synth_instance_accessor)) {
+                        match setup_audio_stream(device_index, sample_rate, config.routing.num_outputs, config.low_latency, Arc::clone(&pa_synth_instance_accessor)) {
                             Ok(new_stream) => {
                                 stream = Some(new_stream);
                                 debug!("Output stream reinitialized successfully");
@@ -686,6 +1083,17 @@ pub fn start_resynth_thread(
                         gain: config.gain,
                         freq_scale: config.freq_scale,
                         update_rate: config.update_rate,
+                        onset_boundaries: config.onset_boundaries,
+                        routing: config.routing.clone(),
+                        noise_level: config.noise_level,
+                        transpose_semitones: config.transpose_semitones,
+                        transpose_cents: config.transpose_cents,
+                        preserve_formants: config.preserve_formants,
+                        transforms: config.transforms.clone(),
+                        low_latency: config.low_latency,
+                        latency: Arc::clone(&config.latency),
+                        limiter: config.limiter,
+                        meters: Arc::clone(&config.meters),
                         needs_restart: Arc::new(AtomicBool::new(false)),
                         needs_stop: Arc::new(AtomicBool::new(false)),
                         output_sample_rate: Arc::clone(&config.output_sample_rate),
@@ -722,6 +1130,8 @@ pub fn start_resynth_thread(
                     match setup_audio_stream(
                         device_index,
                         output_sample_rate,
+                        num_outputs(&resynth_config_accessor),
+                        low_latency(&resynth_config_accessor),
                         Arc::clone(&pa_synth_instance_accessor),
                     ) {
                         Ok(s) => {
@@ -777,6 +1187,8 @@ pub fn start_resynth_thread(
 fn setup_audio_stream(
     device_index: pa::DeviceIndex,
     sample_rate: f64,
+    num_outputs: usize, // Interleaved output channels to open
+    low_latency: bool,  // Small buffer and low device latency for the live bank
     synth_instance: Arc<Mutex<WaveSynth>>, // WaveSynth instance for the audio callback
 ) -> Result<pa::Stream<pa::NonBlocking, pa::Output<f32>>, anyhow::Error> {
     let pa_ctx = pa::PortAudio::new()?;
@@ -786,13 +1198,14 @@ fn setup_audio_stream(
     info!(target: "resynth::pa_setup", "Setting up PA stream for device: {}, Output Channels: {}, Default SR: {}",
         device_info.name, device_info.max_output_channels, device_info.default_sample_rate);
 
-    if device_info.max_output_channels < 2 {
-        return Err(anyhow::anyhow!("Selected output device {} does not support stereo output (has {} channels).", device_info.name, device_info.max_output_channels));
+    let num_outputs = num_outputs.clamp(1, MAX_OUTPUT_CHANNELS);
+    if (device_info.max_output_channels as usize) < num_outputs {
+        return Err(anyhow::anyhow!("Selected output device {} does not support {} output channels (has {} channels).", device_info.name, num_outputs, device_info.max_output_channels));
     }
 
     // For HDA devices, use specific settings
     let is_hda = device_info.name.contains("HDA");
-    let latency = if is_hda {
+    let latency = if is_hda && !low_latency {
         // HDA devices work better with higher latency
         info!(target: "resynth::pa_setup", "Using higher latency for HDA device");
         device_info.default_high_output_latency
@@ -802,7 +1215,7 @@ fn setup_audio_stream(
 
     let output_params = pa::StreamParameters::<f32>::new(
         device_index,
-        2, // Force stereo output
+        num_outputs as i32, // Channel count from the output routing
         true, // Interleaved
         latency
     );
@@ -815,8 +1228,10 @@ fn setup_audio_stream(
               sample_rate, device_info.default_sample_rate);
     }
     
-    // Use a larger buffer size for high sample rates
-    let buffer_frames = if sample_rate > 96000.0 {
+    // Use a larger buffer size for high sample rates, unless latency matters more
+    let buffer_frames = if low_latency {
+        LOW_LATENCY_BUFFER_FRAMES
+    } else if sample_rate > 96000.0 {
         // For high sample rates, use larger buffer
         4096
     } else if sample_rate > 48000.0 {
@@ -837,32 +1252,40 @@ fn setup_audio_stream(
         buffer_frames
     );
 
+    // The synth's segments are mixed into however many channels this stream has
+    if let Ok(mut synth) = synth_instance.lock() {
+        synth.set_num_outputs(num_outputs);
+    }
+
+    let callback_synth = Arc::clone(&synth_instance);
     let callback = move |pa::OutputStreamCallbackArgs { buffer, frames, .. }| {
-        // Ensure buffer has enough space for stereo: frames * 2
-        if buffer.len() < frames * 2 {
-            error!(target: "resynth::pa_callback", "PA callback buffer too small! Expected {}, got {}. Filling with silence.", frames * 2, buffer.len());
+        // Ensure buffer has enough space for every channel: frames * num_outputs
+        if buffer.len() < frames * num_outputs {
+            error!(target: "resynth::pa_callback", "PA callback buffer too small! Expected {}, got {}. Filling with silence.", frames * num_outputs, buffer.len());
             for sample in buffer.iter_mut() { *sample = 0.0; }
             return pa::Continue;
         }
         
-        // Assuming buffer is mutable slice for stereo interleaved data
-        if let Ok(mut synth) = synth_instance.lock() {
-            synth.process_buffer(buffer); // process_buffer now handles stereo internally
+        // Buffer is a mutable slice of interleaved data, num_outputs samples per frame
+        if let Ok(mut synth) = callback_synth.lock() {
+            synth.process_buffer(buffer);
         } else {
             // Failed to lock synth, fill with silence to avoid PA issues
             warn!(target: "resynth::pa_callback", "Failed to lock WaveSynth in PA callback. Outputting silence.");
-            for sample_pair in buffer.chunks_mut(2) {
-                if sample_pair.len() == 2 {
-                    sample_pair[0] = 0.0; // L
-                    sample_pair[1] = 0.0; // R
-                }
-            }
+            buffer.fill(0.0);
         }
         pa::Continue
     };
 
     let mut stream = pa_ctx.open_non_blocking_stream(stream_settings, callback)
         .map_err(|e| anyhow::anyhow!("Failed to open PA non-blocking stream: {}", e))?;
+
+    // The latency report counts the output path as the opened stream reports it
+    let output_latency = stream.info().output_latency as f32;
+    info!(target: "resynth::pa_setup", "Output latency {:.1} ms (low latency mode: {})", output_latency * 1000.0, low_latency);
+    if let Ok(mut synth) = synth_instance.lock() {
+        synth.set_low_latency(low_latency, output_latency);
+    }
     
     stream.start().map_err(|e| anyhow::anyhow!("Failed to start PA stream: {}", e))?;
     
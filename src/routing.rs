@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+
+pub const MAX_OUTPUT_CHANNELS: usize = 32;
+
+// 2^32 divided by the golden ratio: Fibonacci hashing scatters consecutive track IDs evenly
+const TRACK_SCATTER: u32 = 2_654_435_769;
+
+/// How partials within a channel are spread across the outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum SpreadMode {
+    /// Every partial follows its channel's routing
+    #[default]
+    Off,
+    /// Lowest to highest partial across the outputs, on a log scale
+    Frequency,
+    /// Harmonics take turns on the outputs: alternating left/right in stereo
+    Harmonic,
+    /// Each track keeps a fixed place, scattered by its ID
+    Track,
+}
+
+impl SpreadMode {
+    pub const ALL: [SpreadMode; 4] = [SpreadMode::Off, SpreadMode::Frequency, SpreadMode::Harmonic, SpreadMode::Track];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpreadMode::Off => "Off",
+            SpreadMode::Frequency => "Frequency",
+            SpreadMode::Harmonic => "Harmonic",
+            SpreadMode::Track => "Track",
+        }
+    }
+}
+
+/// A partial's contribution to one output: where it came from and its routed amplitude.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoutedPartial {
+    pub input: usize,
+    pub index: usize,
+    pub freq: f32,
+    pub amp: f32,
+}
+
+/// Analysis labels of one partial, used to place it when spreading.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PartialTag {
+    pub harmonic: Option<u32>,
+    pub track: Option<u32>,
+}
+
+/// Where each analysed input channel is heard: a linear gain per output channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputRouting {
+    pub num_outputs: usize,
+    /// Gains indexed as `gains[input][output]`. Inputs without a row follow the default
+    /// layout: the stereo fold-down on two outputs, otherwise one input per output.
+    pub gains: Vec<Vec<f32>>,
+    pub spread_mode: SpreadMode,
+    /// 0 keeps partials at their channel's position, 1 spreads them over every output
+    pub spread_width: f32,
+}
+
+impl Default for OutputRouting {
+    fn default() -> Self {
+        Self { num_outputs: 2, gains: Vec::new(), spread_mode: SpreadMode::Off, spread_width: 1.0 }
+    }
+}
+
+impl OutputRouting {
+    /// The original fold-down: even inputs left, odd inputs right, each side divided by
+    /// the number of inputs feeding it.
+    pub fn stereo(num_inputs: usize) -> Self {
+        let left_sources = num_inputs.div_ceil(2).max(1) as f32;
+        let right_sources = (num_inputs / 2).max(1) as f32;
+        let gains = (0..num_inputs)
+            .map(|input| if input % 2 == 0 { vec![1.0 / left_sources, 0.0] } else { vec![0.0, 1.0 / right_sources] })
+            .collect();
+        Self { num_outputs: 2, gains, ..Self::default() }
+    }
+
+    /// Input `i` to output `i`, wrapping when there are more inputs than outputs.
+    pub fn one_to_one(num_inputs: usize, num_outputs: usize) -> Self {
+        let num_outputs = num_outputs.clamp(1, MAX_OUTPUT_CHANNELS);
+        let gains = (0..num_inputs)
+            .map(|input| (0..num_outputs).map(|output| if input % num_outputs == output { 1.0 } else { 0.0 }).collect())
+            .collect();
+        Self { num_outputs, gains, ..Self::default() }
+    }
+
+    /// Inputs spaced evenly from the first output to the last, panned with equal power
+    /// where they fall between two speakers.
+    pub fn spread(num_inputs: usize, num_outputs: usize) -> Self {
+        let num_outputs = num_outputs.clamp(1, MAX_OUTPUT_CHANNELS);
+        let span = (num_outputs - 1) as f32;
+        let gains = (0..num_inputs)
+            .map(|input| {
+                let position = if num_inputs > 1 { span * input as f32 / (num_inputs - 1) as f32 } else { span / 2.0 };
+                pan_gains(position, num_outputs)
+            })
+            .collect();
+        Self { num_outputs, gains, ..Self::default() }
+    }
+
+    /// Gain row of every input, filling inputs without a row from the default layout and
+    /// trimming or padding rows to the output count.
+    pub fn effective_gains(&self, num_inputs: usize) -> Vec<Vec<f32>> {
+        let num_outputs = self.num_outputs.clamp(1, MAX_OUTPUT_CHANNELS);
+        let default = if num_outputs == 2 { Self::stereo(num_inputs) } else { Self::one_to_one(num_inputs, num_outputs) };
+        (0..num_inputs)
+            .map(|input| {
+                let mut row = self.gains.get(input).unwrap_or(&default.gains[input]).clone();
+                row.resize(num_outputs, 0.0);
+                row
+            })
+            .collect()
+    }
+
+    /// Mixes each input's partials into every output it is routed to, scaled by the route
+    /// gain. Each output is then scaled so its amplitudes sum to at most 1.
+    pub fn route_partials(&self, partials: &[Vec<(f32, f32)>], tags: &[Vec<PartialTag>]) -> Vec<Vec<(f32, f32)>> {
+        self.routed(partials, tags)
+            .into_iter()
+            .map(|output| output.into_iter().map(|routed| (routed.freq, routed.amp)).collect())
+            .collect()
+    }
+
+    /// `route_partials`, keeping which input partial each routed partial came from. With a
+    /// spread mode, each partial is panned between its channel's position and its own place
+    /// along the outputs, keeping the channel's overall level.
+    pub fn routed(&self, partials: &[Vec<(f32, f32)>], tags: &[Vec<PartialTag>]) -> Vec<Vec<RoutedPartial>> {
+        let gains = self.effective_gains(partials.len());
+        let num_outputs = self.num_outputs.clamp(1, MAX_OUTPUT_CHANNELS);
+        let span = (num_outputs - 1) as f32;
+        let width = self.spread_width.clamp(0.0, 1.0);
+        let mut outputs: Vec<Vec<RoutedPartial>> = vec![Vec::new(); num_outputs];
+        for (input, (input_partials, row)) in partials.iter().zip(&gains).enumerate() {
+            let no_tags = Vec::new();
+            let places = self.spread_mode.places(input_partials, tags.get(input).unwrap_or(&no_tags), num_outputs);
+            let Some(places) = places else {
+                for (output, &gain) in outputs.iter_mut().zip(row) {
+                    if gain > 0.0 {
+                        output.extend(input_partials.iter()
+                            .enumerate()
+                            .map(|(index, &(freq, amp))| RoutedPartial { input, index, freq, amp: amp * gain }));
+                    }
+                }
+                continue;
+            };
+
+            let centre = pan_position(row);
+            let level = row.iter().map(|gain| gain * gain).sum::<f32>().sqrt();
+            for (index, (&(freq, amp), place)) in input_partials.iter().zip(places).enumerate() {
+                let position = centre + (place * span - centre) * width;
+                for (output, gain) in outputs.iter_mut().zip(pan_gains(position, num_outputs)) {
+                    if gain > 0.0 {
+                        output.push(RoutedPartial { input, index, freq, amp: amp * gain * level });
+                    }
+                }
+            }
+        }
+        for output in outputs.iter_mut() {
+            let sum: f32 = output.iter().map(|routed| routed.amp.abs()).sum();
+            if sum > 1.0 {
+                for routed in output.iter_mut() {
+                    routed.amp /= sum;
+                }
+            }
+        }
+        outputs
+    }
+}
+
+impl SpreadMode {
+    /// Place of each partial along the outputs, from 0 (first) to 1 (last), or `None` when
+    /// partials aren't spread. Partials without the label a mode needs sit in the middle.
+    fn places(&self, partials: &[(f32, f32)], tags: &[PartialTag], num_outputs: usize) -> Option<Vec<f32>> {
+        let tag = |index: usize| tags.get(index).copied().unwrap_or_default();
+        match self {
+            SpreadMode::Off => None,
+            SpreadMode::Frequency => {
+                let (lowest, highest) = partials.iter()
+                    .filter(|(freq, _)| *freq > 0.0)
+                    .fold((f32::INFINITY, 0.0f32), |(low, high), &(freq, _)| (low.min(freq), high.max(freq)));
+                let range = (highest / lowest).log2();
+                Some(partials.iter()
+                    .map(|&(freq, _)| if range > 0.0 && freq > 0.0 { (freq / lowest).log2() / range } else { 0.5 })
+                    .collect())
+            }
+            SpreadMode::Harmonic => Some((0..partials.len())
+                .map(|index| match tag(index).harmonic {
+                    Some(harmonic) if harmonic > 0 && num_outputs > 1 => {
+                        ((harmonic as usize - 1) % num_outputs) as f32 / (num_outputs - 1) as f32
+                    }
+                    _ => 0.5,
+                })
+                .collect()),
+            SpreadMode::Track => Some((0..partials.len())
+                .map(|index| match tag(index).track {
+                    Some(track) => track.wrapping_mul(TRACK_SCATTER) as f32 / u32::MAX as f32,
+                    None => 0.5,
+                })
+                .collect()),
+        }
+    }
+}
+
+/// Gain row placing a source at `position` along the outputs (0 is the first output,
+/// `num_outputs - 1` the last), shared between the two nearest with an equal-power law.
+pub fn pan_gains(position: f32, num_outputs: usize) -> Vec<f32> {
+    let mut gains = vec![0.0; num_outputs.max(1)];
+    let position = position.clamp(0.0, (gains.len() - 1) as f32);
+    let lower = (position.floor() as usize).min(gains.len() - 1);
+    let fraction = position - lower as f32;
+    let angle = fraction * std::f32::consts::FRAC_PI_2;
+    gains[lower] = angle.cos();
+    if lower + 1 < gains.len() {
+        gains[lower + 1] = angle.sin();
+    }
+    gains
+}
+
+/// Position along the outputs that `pan_gains` would turn into this row: the first routed
+/// output, moved towards the next one by the share of power it has.
+pub fn pan_position(gains: &[f32]) -> f32 {
+    let Some(lower) = gains.iter().position(|&gain| gain > 0.0) else {
+        return 0.0;
+    };
+    let upper = gains.get(lower + 1).copied().unwrap_or(0.0).max(0.0);
+    lower as f32 + upper.atan2(gains[lower]) / std::f32::consts::FRAC_PI_2
+}
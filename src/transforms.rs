@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use crate::tuning::Tuning;
+
+/// One step of the chain of transforms applied to the partials before resynthesis.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PartialTransform {
+    /// Harmonic spacing multiplied by `factor` about f0: above 1 stretches, below compresses
+    Stretch { factor: f32 },
+    /// Pull towards the nearest note of the tuning, all the way at strength 1
+    Quantize { strength: f32 },
+    /// Mirror on a log-frequency axis about `pivot` Hz, so high partials become low ones
+    Invert { pivot: f32 },
+    /// Add `hz` to every partial, which breaks harmonic ratios unlike pitch scaling
+    Shift { hz: f32 },
+}
+
+impl PartialTransform {
+    /// One of each kind with neutral-ish starting values, for adding to a chain.
+    pub const ALL: [PartialTransform; 4] = [
+        PartialTransform::Stretch { factor: 1.0 },
+        PartialTransform::Quantize { strength: 1.0 },
+        PartialTransform::Invert { pivot: 1000.0 },
+        PartialTransform::Shift { hz: 0.0 },
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PartialTransform::Stretch { .. } => "Stretch",
+            PartialTransform::Quantize { .. } => "Quantize",
+            PartialTransform::Invert { .. } => "Invert",
+            PartialTransform::Shift { .. } => "Shift",
+        }
+    }
+
+    /// New frequency for a partial at `freq`, given the channel's fundamental.
+    fn apply(&self, freq: f32, f0: f32, tuning: &Tuning) -> f32 {
+        match *self {
+            PartialTransform::Stretch { factor } => f0 + (freq - f0) * factor,
+            PartialTransform::Quantize { strength } => match tuning.nearest_note(freq) {
+                Some(note) => freq * 2f32.powf(-note.cents * strength.clamp(0.0, 1.0) / 1200.0),
+                None => freq,
+            },
+            PartialTransform::Invert { pivot } => pivot * pivot / freq,
+            PartialTransform::Shift { hz } => freq + hz,
+        }
+    }
+}
+
+/// Runs every channel's partials through the chain in order. `f0s` holds each channel's
+/// fundamental, falling back to its lowest partial. Partials pushed to 0 Hz or below, or
+/// past `max_freq`, become empty slots so the partial layout stays the same.
+pub fn apply_chain(
+    chain: &[PartialTransform],
+    partials: &mut [Vec<(f32, f32)>],
+    f0s: &[Option<f32>],
+    tuning: &Tuning,
+    max_freq: f32,
+) {
+    if chain.is_empty() {
+        return;
+    }
+    for (channel, channel_partials) in partials.iter_mut().enumerate() {
+        let lowest = channel_partials.iter()
+            .map(|&(freq, _)| freq)
+            .filter(|&freq| freq > 0.0)
+            .fold(f32::INFINITY, f32::min);
+        let f0 = f0s.get(channel).copied().flatten().filter(|&f0| f0 > 0.0).unwrap_or(lowest);
+
+        for partial in channel_partials.iter_mut().filter(|(freq, _)| *freq > 0.0) {
+            let freq = chain.iter().fold(partial.0, |freq, transform| {
+                if freq > 0.0 { transform.apply(freq, f0, tuning) } else { freq }
+            });
+            *partial = if freq > 0.0 && freq <= max_freq { (freq, partial.1) } else { (0.0, 0.0) };
+        }
+    }
+}
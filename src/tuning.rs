@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use anyhow::{Result, anyhow};
+use log::warn;
+
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+// 5-limit just intonation on C
+const JUST_RATIOS: [f64; 12] = [
+    1.0, 16.0 / 15.0, 9.0 / 8.0, 6.0 / 5.0, 5.0 / 4.0, 4.0 / 3.0,
+    45.0 / 32.0, 3.0 / 2.0, 8.0 / 5.0, 5.0 / 3.0, 9.0 / 5.0, 15.0 / 8.0,
+];
+// Tonic is C4; octave numbers count periods from there
+const TONIC_OCTAVE: i32 = 4;
+// A sits nine semitones above C in 12-tone equal temperament
+const A_DEGREE: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum TuningSystem {
+    #[default]
+    EqualTemperament,
+    JustIntonation,
+    Scala,
+}
+
+/// User-facing tuning settings, stored in presets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuningConfig {
+    pub a4: f32,
+    pub system: TuningSystem,
+    pub scala_file: Option<String>,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        Self {
+            a4: 440.0,
+            system: TuningSystem::EqualTemperament,
+            scala_file: None,
+        }
+    }
+}
+
+/// A scale read from a Scala `.scl` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalaScale {
+    pub description: String,
+    /// Degrees after the implicit 1/1, in cents; the last one is the period
+    pub pitches: Vec<f64>,
+}
+
+impl ScalaScale {
+    pub fn load(path: &str) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| anyhow!("Failed to parse {}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim_start().starts_with('!'));
+        let description = lines.next().ok_or_else(|| anyhow!("missing description line"))?.trim().to_string();
+        let count: usize = lines.next()
+            .ok_or_else(|| anyhow!("missing note count"))?
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("invalid note count"))?;
+
+        let pitches = lines
+            .filter_map(|line| line.split_whitespace().next())
+            .take(count)
+            .map(parse_scala_pitch)
+            .collect::<Result<Vec<f64>>>()?;
+        if pitches.len() != count || count == 0 {
+            return Err(anyhow!("expected {} pitches, found {}", count, pitches.len()));
+        }
+        if pitches.last().map_or(true, |&period| period <= 0.0) {
+            return Err(anyhow!("period must be above 1/1"));
+        }
+        Ok(Self { description, pitches })
+    }
+}
+
+/// Parses one Scala pitch: cents if it contains a '.', otherwise a ratio or integer.
+fn parse_scala_pitch(token: &str) -> Result<f64> {
+    if token.contains('.') {
+        return token.parse::<f64>().map_err(|_| anyhow!("invalid cents value '{}'", token));
+    }
+    let (numerator, denominator) = match token.split_once('/') {
+        Some((n, d)) => (n, d),
+        None => (token, "1"),
+    };
+    let numerator: f64 = numerator.parse().map_err(|_| anyhow!("invalid ratio '{}'", token))?;
+    let denominator: f64 = denominator.parse().map_err(|_| anyhow!("invalid ratio '{}'", token))?;
+    if numerator <= 0.0 || denominator <= 0.0 {
+        return Err(anyhow!("invalid ratio '{}'", token));
+    }
+    Ok(1200.0 * (numerator / denominator).log2())
+}
+
+/// Nearest scale note to a frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteReading {
+    pub name: String,
+    pub octave: i32,
+    /// Deviation of the measured frequency from the note, in cents
+    pub cents: f32,
+    /// Frequency of the note itself
+    pub note_freq: f32,
+}
+
+impl NoteReading {
+    pub fn label(&self) -> String {
+        format!("{}{} {:+.1}c", self.name, self.octave, self.cents)
+    }
+}
+
+/// A resolved tuning: scale degrees in cents above the tonic (C4) and its frequency.
+#[derive(Debug, Clone)]
+pub struct Tuning {
+    pub config: TuningConfig,
+    degrees: Vec<f64>,
+    period: f64,
+    names: Vec<String>,
+    tonic: f64,
+}
+
+impl Tuning {
+    /// Builds the tuning, falling back to equal temperament if the Scala file can't be read.
+    pub fn new(config: TuningConfig) -> Self {
+        let (degrees, period): (Vec<f64>, f64) = match config.system {
+            TuningSystem::EqualTemperament => ((0..12).map(|i| i as f64 * 100.0).collect(), 1200.0),
+            TuningSystem::JustIntonation => (JUST_RATIOS.iter().map(|r| 1200.0 * r.log2()).collect(), 1200.0),
+            TuningSystem::Scala => {
+                let scale = config.scala_file.as_deref()
+                    .ok_or_else(|| anyhow!("no Scala file selected"))
+                    .and_then(ScalaScale::load);
+                match scale {
+                    Ok(scale) => {
+                        let period = *scale.pitches.last().unwrap();
+                        let mut degrees = vec![0.0];
+                        degrees.extend(scale.pitches[..scale.pitches.len() - 1].iter().copied());
+                        (degrees, period)
+                    }
+                    Err(e) => {
+                        warn!("Could not load Scala scale, using equal temperament: {}", e);
+                        ((0..12).map(|i| i as f64 * 100.0).collect(), 1200.0)
+                    }
+                }
+            }
+        };
+
+        let names: Vec<String> = if degrees.len() == 12 {
+            NOTE_NAMES.iter().map(|n| n.to_string()).collect()
+        } else {
+            (0..degrees.len()).map(|i| format!("d{}", i)).collect()
+        };
+
+        let tonic = tonic_for(&degrees, config.a4);
+        Self { config, degrees, period, names, tonic }
+    }
+
+    /// Applies new settings, only reading the Scala file again if the system or file changed.
+    pub fn update(&mut self, config: TuningConfig) {
+        if config.system == self.config.system && config.scala_file == self.config.scala_file {
+            self.tonic = tonic_for(&self.degrees, config.a4);
+            self.config = config;
+        } else {
+            *self = Self::new(config);
+        }
+    }
+
+    /// Nearest note of the scale to `freq`.
+    pub fn nearest_note(&self, freq: f32) -> Option<NoteReading> {
+        if freq <= 0.0 {
+            return None;
+        }
+        let cents_above_tonic = 1200.0 * (freq as f64 / self.tonic).log2();
+        let period_index = (cents_above_tonic / self.period).floor();
+        let within = cents_above_tonic - period_index * self.period;
+
+        // Candidates are this period's degrees plus the next period's first degree
+        let (degree, periods, note_cents) = self.degrees.iter()
+            .enumerate()
+            .map(|(i, &d)| (i, 0, d))
+            .chain(std::iter::once((0, 1, self.period)))
+            .min_by(|a, b| (within - a.2).abs().partial_cmp(&(within - b.2).abs()).unwrap_or(std::cmp::Ordering::Equal))?;
+
+        let note_total = period_index * self.period + note_cents;
+        Some(NoteReading {
+            name: self.names[degree].clone(),
+            octave: TONIC_OCTAVE + period_index as i32 + periods,
+            cents: (within - note_cents) as f32,
+            note_freq: (self.tonic * 2f64.powf(note_total / 1200.0)) as f32,
+        })
+    }
+
+    /// Every scale note between `min_freq` and `max_freq`, as (frequency, name, octave).
+    pub fn notes_in_range(&self, min_freq: f32, max_freq: f32) -> Vec<(f32, String, i32)> {
+        let min_freq = min_freq.max(1.0) as f64;
+        let max_freq = max_freq as f64;
+        if max_freq <= min_freq {
+            return Vec::new();
+        }
+        let first_period = (1200.0 * (min_freq / self.tonic).log2() / self.period).floor() as i32;
+        let last_period = (1200.0 * (max_freq / self.tonic).log2() / self.period).ceil() as i32;
+
+        let mut notes = Vec::new();
+        for period_index in first_period..=last_period {
+            for (degree, &cents) in self.degrees.iter().enumerate() {
+                let freq = self.tonic * 2f64.powf((period_index as f64 * self.period + cents) / 1200.0);
+                if freq >= min_freq && freq <= max_freq {
+                    notes.push((freq as f32, self.names[degree].clone(), TONIC_OCTAVE + period_index));
+                }
+            }
+        }
+        notes
+    }
+}
+
+/// Tonic frequency that puts A4 on `a4`; scales without a 12-note layout take the
+/// equal-tempered C4 for that reference.
+fn tonic_for(degrees: &[f64], a4: f32) -> f64 {
+    let a_cents = if degrees.len() == 12 { degrees[A_DEGREE] } else { A_DEGREE as f64 * 100.0 };
+    a4 as f64 / 2f64.powf(a_cents / 1200.0)
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::new(TuningConfig::default())
+    }
+}
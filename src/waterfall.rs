@@ -0,0 +1,249 @@
+use std::collections::VecDeque;
+use egui::{Color32, ColorImage, TextureHandle, TextureOptions};
+use serde::{Deserialize, Serialize};
+use crate::plot::{SpectrographSlice, axis_to_frequency, frequency_to_axis};
+
+// Texture size: time columns across the visible span, frequency rows across the analysis range
+const WATERFALL_COLUMNS: usize = 512;
+const WATERFALL_ROWS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Colormap {
+    /// Blue through green to red, as the partial spectrograph always used
+    #[default]
+    Classic,
+    Viridis,
+    Magma,
+    Grayscale,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [Colormap::Classic, Colormap::Viridis, Colormap::Magma, Colormap::Grayscale];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Colormap::Classic => "Classic",
+            Colormap::Viridis => "Viridis",
+            Colormap::Magma => "Magma",
+            Colormap::Grayscale => "Grayscale",
+        }
+    }
+
+    /// Colour for an intensity between 0 and 1.
+    pub fn color(&self, intensity: f32) -> Color32 {
+        let t = intensity.clamp(0.0, 1.0);
+        match self {
+            Colormap::Classic => Color32::from_rgb(
+                (255.0 * t) as u8,
+                (255.0 * (1.0 - (t - 0.5).abs() * 2.0).max(0.0)) as u8,
+                (255.0 * (1.0 - t)) as u8,
+            ),
+            Colormap::Viridis => interpolate(&[[68, 1, 84], [59, 82, 139], [33, 145, 140], [94, 201, 98], [253, 231, 37]], t),
+            Colormap::Magma => interpolate(&[[0, 0, 4], [81, 18, 124], [183, 55, 121], [252, 137, 97], [252, 253, 191]], t),
+            Colormap::Grayscale => {
+                let level = (255.0 * t) as u8;
+                Color32::from_rgb(level, level, level)
+            }
+        }
+    }
+}
+
+/// Linear interpolation between evenly spaced colour stops.
+fn interpolate(stops: &[[u8; 3]], t: f32) -> Color32 {
+    let position = t * (stops.len() - 1) as f32;
+    let index = (position as usize).min(stops.len() - 2);
+    let fraction = position - index as f32;
+    let channel = |c: usize| {
+        let (a, b) = (stops[index][c] as f32, stops[index + 1][c] as f32);
+        (a + (b - a) * fraction).round() as u8
+    };
+    Color32::from_rgb(channel(0), channel(1), channel(2))
+}
+
+/// Display settings for the waterfall spectrogram.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WaterfallConfig {
+    pub time_span: f32,        // Seconds of history across the plot
+    pub colormap: Colormap,
+    pub floor_dbfs: f32,       // dBFS drawn at the bottom of the colormap
+    pub ceiling_dbfs: f32,     // dBFS drawn at the top of the colormap
+    pub channel: Option<usize>, // Single channel to show, or the loudest of all channels
+    pub show_tracks: bool,     // Overlay partial tracks on the spectrogram
+}
+
+impl Default for WaterfallConfig {
+    fn default() -> Self {
+        Self {
+            time_span: 5.0,
+            colormap: Colormap::Classic,
+            floor_dbfs: -100.0,
+            ceiling_dbfs: 0.0,
+            channel: None,
+            show_tracks: true,
+        }
+    }
+}
+
+/// Everything a drawn column depends on; any change redraws the texture from history.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Layout {
+    config: WaterfallConfig,
+    min_freq: f32,
+    max_freq: f32,
+    log_frequency: bool,
+}
+
+/// Scrolling spectrogram texture built from the spectrograph history.
+///
+/// The texture is a ring buffer of time slots, each `time_span / WATERFALL_COLUMNS`
+/// seconds wide. New slices are painted into their slot and uploaded column by column,
+/// so a frame only sends the columns that changed to the GPU.
+#[derive(Default)]
+pub struct Waterfall {
+    texture: Option<TextureHandle>,
+    image: Option<ColorImage>,
+    layout: Option<Layout>,
+    newest_slot: Option<i64>,
+    last_time: f64,
+}
+
+impl Waterfall {
+    /// Forgets everything drawn so far; the next update repaints from history.
+    pub fn reset(&mut self) {
+        self.layout = None;
+    }
+
+    /// Paints any slices newer than the last update into the texture.
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        history: &VecDeque<SpectrographSlice>,
+        config: &WaterfallConfig,
+        min_freq: f32,
+        max_freq: f32,
+        log_frequency: bool,
+    ) {
+        let layout = Layout { config: *config, min_freq, max_freq, log_frequency };
+        let background = config.colormap.color(0.0);
+        let full_redraw = self.layout != Some(layout) || self.texture.is_none();
+        if full_redraw {
+            self.layout = Some(layout);
+            self.image = Some(ColorImage::new([WATERFALL_COLUMNS, WATERFALL_ROWS], background));
+            self.newest_slot = None;
+            self.last_time = f64::NEG_INFINITY;
+        }
+
+        let slot_duration = (config.time_span.max(0.1) / WATERFALL_COLUMNS as f32) as f64;
+        let mut dirty_columns = Vec::new();
+        // Only slices newer than the last update and inside the visible span need painting
+        let newest_time = history.back().map_or(0.0, |slice| slice.time);
+        let since = self.last_time.max(newest_time - config.time_span as f64);
+        if let Some(image) = self.image.as_mut() {
+            for slice in history.iter().filter(|slice| slice.time > since) {
+                let slot = (slice.time / slot_duration).floor() as i64;
+                let column = render_column(slice, &layout);
+                // Slots skipped since the previous slice repeat this column so the texture has no gaps
+                let first_slot = match self.newest_slot {
+                    Some(newest) if slot > newest => (newest + 1).max(slot - WATERFALL_COLUMNS as i64 + 1),
+                    _ => slot,
+                };
+                for s in first_slot..=slot {
+                    let x = s.rem_euclid(WATERFALL_COLUMNS as i64) as usize;
+                    for (row, &color) in column.iter().enumerate() {
+                        image.pixels[row * WATERFALL_COLUMNS + x] = color;
+                    }
+                    dirty_columns.push(x);
+                }
+                self.newest_slot = Some(self.newest_slot.map_or(slot, |newest| newest.max(slot)));
+                self.last_time = slice.time;
+            }
+        }
+
+        let Some(image) = self.image.as_ref() else { return };
+        match self.texture.as_mut() {
+            Some(texture) if !full_redraw => {
+                dirty_columns.sort_unstable();
+                dirty_columns.dedup();
+                for x in dirty_columns {
+                    let column = ColorImage {
+                        size: [1, WATERFALL_ROWS],
+                        pixels: (0..WATERFALL_ROWS).map(|row| image.pixels[row * WATERFALL_COLUMNS + x]).collect(),
+                    };
+                    texture.set_partial([x, 0], column, TextureOptions::NEAREST);
+                }
+            }
+            Some(texture) => texture.set(image.clone(), TextureOptions::NEAREST),
+            None => self.texture = Some(ctx.load_texture("waterfall", image.clone(), TextureOptions::NEAREST)),
+        }
+    }
+
+    /// Draws the texture with time in milliseconds on x and the frequency axis on y.
+    /// The ring buffer wraps, so the oldest and newest parts are drawn as two images.
+    pub fn show(&self, plot_ui: &mut egui_plot::PlotUi) {
+        let (Some(texture), Some(layout), Some(newest)) = (&self.texture, self.layout, self.newest_slot) else {
+            return;
+        };
+        let slot_ms = (layout.config.time_span.max(0.1) / WATERFALL_COLUMNS as f32) as f64 * 1000.0;
+        let y_min = frequency_to_axis(layout.min_freq as f64, layout.log_frequency);
+        let y_max = frequency_to_axis(layout.max_freq as f64, layout.log_frequency);
+        let oldest = newest + 1 - WATERFALL_COLUMNS as i64;
+        let split = (newest + 1).rem_euclid(WATERFALL_COLUMNS as i64) as usize;
+
+        // (first texture column, column count, first slot) of each part, oldest first
+        let parts = [(split, WATERFALL_COLUMNS - split, oldest), (0, split, oldest + (WATERFALL_COLUMNS - split) as i64)];
+        for (first_column, count, first_slot) in parts {
+            if count == 0 {
+                continue;
+            }
+            let x_start = first_slot as f64 * slot_ms;
+            let width = count as f64 * slot_ms;
+            let uv = egui::Rect::from_min_max(
+                egui::pos2(first_column as f32 / WATERFALL_COLUMNS as f32, 0.0),
+                egui::pos2((first_column + count) as f32 / WATERFALL_COLUMNS as f32, 1.0),
+            );
+            plot_ui.image(
+                egui_plot::PlotImage::new(
+                    texture.id(),
+                    egui_plot::PlotPoint::new(x_start + width / 2.0, (y_min + y_max) / 2.0),
+                    egui::vec2(width as f32, (y_max - y_min) as f32),
+                )
+                .uv(uv)
+            );
+        }
+    }
+}
+
+/// Colours one texture column from a slice, top row at the highest frequency. Each row
+/// shows the loudest bin inside its frequency band, or the nearest bin when bands are
+/// narrower than bins.
+fn render_column(slice: &SpectrographSlice, layout: &Layout) -> Vec<Color32> {
+    let config = &layout.config;
+    let axis_min = frequency_to_axis(layout.min_freq as f64, layout.log_frequency);
+    let axis_max = frequency_to_axis(layout.max_freq as f64, layout.log_frequency);
+    let row_height = (axis_max - axis_min) / WATERFALL_ROWS as f64;
+    let range = (config.ceiling_dbfs - config.floor_dbfs).max(1.0);
+
+    let channels: Vec<&Vec<f32>> = match config.channel {
+        Some(channel) => slice.spectrum.get(channel).into_iter().collect(),
+        None => slice.spectrum.iter().collect(),
+    };
+
+    (0..WATERFALL_ROWS)
+        .map(|row| {
+            let band_bottom = axis_min + row_height * (WATERFALL_ROWS - 1 - row) as f64;
+            let low = axis_to_frequency(band_bottom, layout.log_frequency) as f32 / slice.bin_hz;
+            let high = axis_to_frequency(band_bottom + row_height, layout.log_frequency) as f32 / slice.bin_hz;
+            let (first, last) = if high.floor() >= low.ceil() {
+                (low.ceil() as usize, high.floor() as usize)
+            } else {
+                let nearest = ((low + high) / 2.0).round() as usize;
+                (nearest, nearest)
+            };
+            let db = channels.iter()
+                .flat_map(|bins| bins.iter().take(last + 1).skip(first))
+                .fold(f32::NEG_INFINITY, |loudest, &db| loudest.max(db));
+            config.colormap.color((db - config.floor_dbfs) / range)
+        })
+        .collect()
+}